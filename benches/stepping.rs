@@ -0,0 +1,84 @@
+//! Measures the per-frame cost of advancing clocks and draining their fixed steps.
+//!
+//! Run with `cargo bench`. Criterion isn't used to keep the crate free of dependencies, so
+//! this is a small harness of its own: every benchmark is warmed up, then timed over many
+//! samples whose iteration count is calibrated to a fixed duration. The median of the samples
+//! is reported with the spread between the fastest and slowest quartiles, so a few samples
+//! slowed by the scheduler don't skew the result.
+
+use game_clock::{CatchUp, Time};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const WARM_UP: Duration = Duration::from_millis(300);
+const SAMPLE: Duration = Duration::from_millis(10);
+const SAMPLES: usize = 51;
+
+/// Measures the time `f` takes over `iterations` calls, in nanoseconds per call.
+fn sample<F: FnMut()>(f: &mut F, iterations: u64) -> f64 {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed().as_nanos() as f64 / iterations as f64
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    // Warm up caches, branch predictors and CPU frequency, while calibrating the number of
+    // iterations of a sample.
+    let mut iterations = 1;
+    let start = Instant::now();
+    while start.elapsed() < WARM_UP {
+        let per_iteration = sample(&mut f, iterations);
+        let wanted = SAMPLE.as_nanos() as f64 / per_iteration.max(0.1);
+        iterations = (wanted as u64).clamp(1, iterations * 2);
+    }
+    let mut samples: Vec<f64> = (0..SAMPLES).map(|_| sample(&mut f, iterations)).collect();
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let quartile = |q: usize| samples[(SAMPLES - 1) * q / 4];
+    println!(
+        "{:<40} {:>9.2} ns/iter  (quartiles {:.2} .. {:.2}, {} x {} iterations)",
+        name,
+        quartile(2),
+        quartile(1),
+        quartile(3),
+        SAMPLES,
+        iterations
+    );
+}
+
+fn main() {
+    let frame = Duration::from_secs_f64(1.0 / 60.0);
+
+    let mut time = Time::default();
+    bench("advance_frame", || {
+        time.advance_frame(black_box(frame));
+    });
+
+    let mut time = Time::default();
+    time.set_fixed_time(Duration::from_secs_f64(1.0 / 120.0));
+    bench("advance_frame + step_fixed_update", || {
+        time.advance_frame(black_box(frame));
+        while time.step_fixed_update() {
+            black_box(&time);
+        }
+    });
+
+    let mut time = Time::default();
+    time.set_time_scale(0.3);
+    time.set_catch_up(CatchUp::Smooth { max_extra_steps: 1 });
+    bench("scaled, smooth catch-up", || {
+        time.advance_frame(black_box(frame));
+        while time.step_fixed_update() {
+            black_box(&time);
+        }
+    });
+
+    let mut clocks = vec![Time::default(); 64];
+    bench("64 clocks", || {
+        for time in clocks.iter_mut() {
+            time.advance_frame(black_box(frame));
+            while time.step_fixed_update() {}
+        }
+    });
+}
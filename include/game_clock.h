@@ -0,0 +1,94 @@
+/* C API of the game_clock crate, enabled with the `ffi` feature.
+ * Kept in sync with src/ffi.rs; can be regenerated with `cbindgen --config cbindgen.toml`. */
+
+#ifndef GAME_CLOCK_H
+#define GAME_CLOCK_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Handle returned when a call could not be scheduled. */
+#define GAME_CLOCK_INVALID_HANDLE UINT64_MAX
+
+/* Opaque countdown. */
+typedef struct GameClockCountdown GameClockCountdown;
+
+/* Opaque scheduler, queuing the handles of the calls that ran. */
+typedef struct GameClockScheduler GameClockScheduler;
+
+/* Opaque clock. */
+typedef struct GameClockTime GameClockTime;
+
+/* The frequently read timing values of a clock, packed in plain integers. */
+typedef struct PackedTime {
+  /* Time elapsed since the last frame, in nanoseconds. */
+  uint64_t delta_nanos;
+  /* Time elapsed since the last frame ignoring the time speed multiplier, in nanoseconds. */
+  uint64_t delta_real_nanos;
+  /* Time elapsed since game start, taking the speed multiplier into account, in nanoseconds. */
+  uint64_t absolute_nanos;
+  /* Time elapsed since game start, ignoring the speed multiplier, in nanoseconds. */
+  uint64_t absolute_real_nanos;
+  /* The current frame number. */
+  uint64_t frame_number;
+} PackedTime;
+
+GameClockTime *game_clock_time_new(uint64_t fixed_step_nanos);
+
+void game_clock_time_free(GameClockTime *time);
+
+void game_clock_time_advance_frame(GameClockTime *time, uint64_t delta_nanos);
+
+bool game_clock_time_step_fixed_update(GameClockTime *time);
+
+bool game_clock_time_set_fixed_time(GameClockTime *time, uint64_t fixed_step_nanos);
+
+bool game_clock_time_set_time_scale(GameClockTime *time, double multiplier);
+
+double game_clock_time_time_scale(const GameClockTime *time);
+
+PackedTime game_clock_time_pack(const GameClockTime *time);
+
+uint64_t game_clock_time_fixed_step_count(const GameClockTime *time);
+
+GameClockCountdown *game_clock_countdown_new(uint64_t duration_nanos, bool real_time);
+
+void game_clock_countdown_free(GameClockCountdown *countdown);
+
+void game_clock_countdown_update(GameClockCountdown *countdown, const GameClockTime *time);
+
+uint64_t game_clock_countdown_remaining(const GameClockCountdown *countdown);
+
+void game_clock_countdown_add_time(GameClockCountdown *countdown, uint64_t nanos);
+
+void game_clock_countdown_remove_time(GameClockCountdown *countdown, uint64_t nanos);
+
+bool game_clock_countdown_just_expired(const GameClockCountdown *countdown);
+
+bool game_clock_countdown_is_expired(const GameClockCountdown *countdown);
+
+GameClockScheduler *game_clock_scheduler_new(bool real_time);
+
+void game_clock_scheduler_free(GameClockScheduler *scheduler);
+
+uint64_t game_clock_scheduler_schedule_after(GameClockScheduler *scheduler, uint64_t delay_nanos);
+
+uint64_t game_clock_scheduler_every(GameClockScheduler *scheduler, uint64_t interval_nanos);
+
+bool game_clock_scheduler_cancel(GameClockScheduler *scheduler, uint64_t handle);
+
+uint64_t game_clock_scheduler_update(GameClockScheduler *scheduler, const GameClockTime *time);
+
+bool game_clock_scheduler_next_fired(GameClockScheduler *scheduler, uint64_t *handle);
+
+uint64_t game_clock_scheduler_len(const GameClockScheduler *scheduler);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* GAME_CLOCK_H */
@@ -0,0 +1,186 @@
+//! Alarms firing after a duration of scaled game time or real time.
+
+use std::time::Duration;
+
+use crate::{Time, TimerMode};
+
+/// Identifies an alarm of an `AlarmClock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AlarmId(u64);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Alarm<E> {
+    id: AlarmId,
+    mode: TimerMode,
+    /// Absolute time of the timeline of `mode` at which the alarm fires.
+    due: Duration,
+    /// Period of a repeating alarm.
+    interval: Option<Duration>,
+    event: E,
+}
+
+/// Alarms that yield user events, each following either scaled game time or real time.
+///
+/// Real time alarms ("after 5 real minutes") keep running while the game is paused or slowed
+/// down, for session length reminders, autosave intervals and daily reward timers. Game time
+/// alarms freeze with the game, for in-game cooldowns.
+///
+/// Call `update` once per frame, after `advance_frame`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlarmClock<E> {
+    alarms: Vec<Alarm<E>>,
+    next_id: u64,
+    /// Scaled absolute time of the last update.
+    game_now: Duration,
+    /// Real absolute time of the last update.
+    real_now: Duration,
+}
+
+impl<E: Clone> AlarmClock<E> {
+    /// Creates an alarm clock with no alarms, starting at the current time of the clock.
+    pub fn new(time: &Time) -> Self {
+        AlarmClock {
+            alarms: vec![],
+            next_id: 0,
+            game_now: time.absolute_time(),
+            real_now: time.absolute_real_time(),
+        }
+    }
+
+    fn now(&self, mode: TimerMode) -> Duration {
+        match mode {
+            TimerMode::GameTime => self.game_now,
+            TimerMode::RealTime => self.real_now,
+        }
+    }
+
+    fn insert(
+        &mut self,
+        after: Duration,
+        interval: Option<Duration>,
+        mode: TimerMode,
+        event: E,
+    ) -> AlarmId {
+        let id = AlarmId(self.next_id);
+        self.next_id += 1;
+        self.alarms.push(Alarm {
+            id,
+            mode,
+            due: self.now(mode) + after,
+            interval,
+            event,
+        });
+        id
+    }
+
+    /// Sets an alarm yielding `event` once `after` has passed on the timeline of `mode`.
+    pub fn set_alarm(&mut self, after: Duration, mode: TimerMode, event: E) -> AlarmId {
+        self.insert(after, None, mode, event)
+    }
+
+    /// Sets an alarm yielding `event` every `interval` on the timeline of `mode`.
+    ///
+    /// ## Panics
+    /// This will panic if interval is zero.
+    pub fn set_repeating(&mut self, interval: Duration, mode: TimerMode, event: E) -> AlarmId {
+        assert!(interval > Duration::from_secs(0));
+        self.insert(interval, Some(interval), mode, event)
+    }
+
+    /// Removes an alarm. Returns false if it already fired or was cancelled.
+    pub fn cancel(&mut self, id: AlarmId) -> bool {
+        let len = self.alarms.len();
+        self.alarms.retain(|alarm| alarm.id != id);
+        self.alarms.len() != len
+    }
+
+    /// Gets the time left before the alarm fires, on its own timeline.
+    pub fn remaining(&self, id: AlarmId) -> Option<Duration> {
+        self.alarms
+            .iter()
+            .find(|alarm| alarm.id == id)
+            .map(|alarm| alarm.due.saturating_sub(self.now(alarm.mode)))
+    }
+
+    /// Gets the time left before the soonest alarm following the timeline of `mode` fires,
+    /// or None if there is none. In idle mode, pass it to `FrameLimiter::wake_before` so that
+    /// real time alarms fire on time.
+    pub fn time_until_next(&self, mode: TimerMode) -> Option<Duration> {
+        self.alarms
+            .iter()
+            .filter(|alarm| alarm.mode == mode)
+            .map(|alarm| alarm.due.saturating_sub(self.now(mode)))
+            .min()
+    }
+
+    /// Gets the number of pending alarms.
+    pub fn len(&self) -> usize {
+        self.alarms.len()
+    }
+
+    /// Checks if there are no pending alarms.
+    pub fn is_empty(&self) -> bool {
+        self.alarms.is_empty()
+    }
+
+    /// Gets the events of the alarms that fired since the last update. Alarms following the
+    /// same timeline are reported in chronological order, game time alarms first. A repeating
+    /// alarm fires once for each of its periods that elapsed.
+    pub fn update(&mut self, time: &Time) -> Vec<E> {
+        self.game_now = time.absolute_time();
+        self.real_now = time.absolute_real_time();
+        let mut fired = vec![];
+        let (game_now, real_now) = (self.game_now, self.real_now);
+        self.alarms.retain_mut(|alarm| {
+            let now = match alarm.mode {
+                TimerMode::GameTime => game_now,
+                TimerMode::RealTime => real_now,
+            };
+            while alarm.due <= now {
+                fired.push((alarm.mode, alarm.due, alarm.id, alarm.event.clone()));
+                match alarm.interval {
+                    Some(interval) => alarm.due += interval,
+                    None => return false,
+                }
+            }
+            true
+        });
+        fired.sort_by_key(|(mode, due, id, _)| (*mode == TimerMode::RealTime, *due, *id));
+        fired.into_iter().map(|(_, _, _, event)| event).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn real_alarms_ignore_pause() {
+        let mut time = Time::default();
+        let mut alarms = AlarmClock::new(&time);
+        let reminder = alarms.set_alarm(Duration::from_secs(300), TimerMode::RealTime, "break");
+        alarms.set_repeating(Duration::from_secs(60), TimerMode::RealTime, "autosave");
+        let cooldown = alarms.set_alarm(Duration::from_secs(5), TimerMode::GameTime, "ready");
+
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(150));
+        assert_eq!(alarms.update(&time), vec!["autosave", "autosave"]);
+        assert_eq!(alarms.remaining(reminder), Some(Duration::from_secs(150)));
+        assert_eq!(alarms.remaining(cooldown), Some(Duration::from_secs(5)));
+        assert_eq!(
+            alarms.time_until_next(TimerMode::RealTime),
+            Some(Duration::from_secs(30))
+        );
+
+        time.set_time_scale(1.0);
+        time.advance_frame(Duration::from_secs(150));
+        assert_eq!(
+            alarms.update(&time),
+            vec!["ready", "autosave", "autosave", "break", "autosave"]
+        );
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms.remaining(reminder), None);
+        assert!(!alarms.cancel(cooldown));
+    }
+}
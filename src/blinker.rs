@@ -0,0 +1,122 @@
+//! On/off cycles driven by game time, for invincibility flashes and blinking cursors.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// A change of state of a `Blinker`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlinkEvent {
+    /// The blinker turned on.
+    On,
+    /// The blinker turned off.
+    Off,
+}
+
+/// Alternates between on and off, starting on.
+///
+/// Because it follows `Time::absolute_time`, it slows down with the time scale and stops
+/// while the game is paused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Blinker {
+    /// Scaled game time spent on each cycle.
+    on: Duration,
+    /// Scaled game time spent off each cycle.
+    off: Duration,
+    /// Scaled absolute time at which the first cycle started.
+    start: Duration,
+    /// State at the last update.
+    was_on: bool,
+}
+
+impl Blinker {
+    /// Creates a blinker staying on for `on` then off for `off`, starting at the current time
+    /// of the clock.
+    ///
+    /// ## Panics
+    /// This will panic if both durations are zero.
+    pub fn new(on: Duration, off: Duration, time: &Time) -> Self {
+        assert!(on + off > Duration::from_secs(0));
+        Blinker {
+            on,
+            off,
+            start: time.absolute_time(),
+            was_on: true,
+        }
+    }
+
+    /// Creates a blinker with a cycle of `period`, on for the `duty` fraction of it.
+    ///
+    /// ## Panics
+    /// This will panic if period is zero, or if duty is not between 0 and 1.
+    pub fn with_duty(period: Duration, duty: f32, time: &Time) -> Self {
+        assert!((0.0..=1.0).contains(&duty));
+        let on = period.mul_f64(f64::from(duty));
+        Blinker::new(on, period - on, time)
+    }
+
+    /// Gets the time spent on each cycle.
+    pub fn on_duration(&self) -> Duration {
+        self.on
+    }
+
+    /// Gets the time spent off each cycle.
+    pub fn off_duration(&self) -> Duration {
+        self.off
+    }
+
+    /// Gets the fraction of each cycle spent on.
+    pub fn duty(&self) -> f32 {
+        (self.on.as_secs_f64() / (self.on + self.off).as_secs_f64()) as f32
+    }
+
+    /// Starts over from the beginning of an on phase, at the current time of the clock.
+    pub fn restart(&mut self, time: &Time) {
+        self.start = time.absolute_time();
+        self.was_on = true;
+    }
+
+    /// Checks if the blinker is on at the current time of the clock.
+    pub fn is_on(&self, time: &Time) -> bool {
+        let elapsed = time
+            .absolute_time()
+            .checked_sub(self.start)
+            .unwrap_or_default();
+        elapsed.as_nanos() % (self.on + self.off).as_nanos() < self.on.as_nanos()
+    }
+
+    /// Gets the change of state since the last update, if any.
+    pub fn update(&mut self, time: &Time) -> Option<BlinkEvent> {
+        let on = self.is_on(time);
+        if on == self.was_on {
+            return None;
+        }
+        self.was_on = on;
+        Some(if on { BlinkEvent::On } else { BlinkEvent::Off })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn blinks_with_game_time() {
+        let mut time = Time::default();
+        let mut blinker = Blinker::with_duty(Duration::from_millis(200), 0.25, &time);
+        assert_eq!(blinker.on_duration(), Duration::from_millis(50));
+        assert!(blinker.is_on(&time));
+
+        time.advance_frame(Duration::from_millis(60));
+        assert_eq!(blinker.update(&time), Some(BlinkEvent::Off));
+        assert_eq!(blinker.update(&time), None);
+
+        // Half speed: 200ms of real time only cover 100ms of the cycle.
+        time.set_time_scale(0.5);
+        time.advance_frame(Duration::from_millis(200));
+        assert!(!blinker.is_on(&time));
+        time.advance_frame(Duration::from_millis(100));
+        assert_eq!(blinker.update(&time), Some(BlinkEvent::On));
+    }
+}
@@ -0,0 +1,512 @@
+//! In-game calendar built on top of the scaled timeline.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::Time;
+
+const HOURS_PER_DAY: u32 = 24;
+const MINUTES_PER_HOUR: u32 = 60;
+const SECONDS_PER_MINUTE: u32 = 60;
+const SECONDS_PER_DAY: u128 = (HOURS_PER_DAY * MINUTES_PER_HOUR * SECONDS_PER_MINUTE) as u128;
+
+/// A point in in-game calendar terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameDateTime {
+    /// Number of in-game days elapsed. The first day is day 0.
+    pub day: u64,
+    /// Hour of the day, from 0 to 23.
+    pub hour: u32,
+    /// Minute of the hour, from 0 to 59.
+    pub minute: u32,
+    /// Second of the minute, from 0 to 59.
+    pub second: u32,
+}
+
+/// A year of in-game days split into equal seasons, with daylight varying across the year.
+///
+/// The shortest day is the first day of the year and the longest is the middle of the year,
+/// so with four seasons the year starts in winter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seasons {
+    days_per_year: u32,
+    count: u32,
+    /// Fraction of the day that is daylight on the shortest day.
+    shortest_daylight: f64,
+    /// Fraction of the day that is daylight on the longest day.
+    longest_daylight: f64,
+}
+
+impl Seasons {
+    /// Creates `count` seasons of equal length in a year of `days_per_year` in-game days.
+    /// Daylight goes from 35% of the shortest day to 65% of the longest.
+    ///
+    /// ## Panics
+    /// This will panic if count is zero or greater than days_per_year.
+    pub fn new(days_per_year: u32, count: u32) -> Self {
+        assert!(count > 0 && count <= days_per_year);
+        Seasons {
+            days_per_year,
+            count,
+            shortest_daylight: 0.35,
+            longest_daylight: 0.65,
+        }
+    }
+
+    /// Gets the number of in-game days in a year.
+    pub fn days_per_year(&self) -> u32 {
+        self.days_per_year
+    }
+
+    /// Gets the number of seasons in a year.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the fractions of the day that are daylight on the shortest and longest days.
+    pub fn daylight_range(&self) -> (f64, f64) {
+        (self.shortest_daylight, self.longest_daylight)
+    }
+
+    /// Sets the fractions of the day that are daylight on the shortest and longest days.
+    ///
+    /// ## Panics
+    /// This will panic if a fraction is not between 0 and 1, or if shortest is greater than
+    /// longest.
+    pub fn set_daylight_range(&mut self, shortest: f64, longest: f64) {
+        assert!((0.0..=1.0).contains(&shortest) && (0.0..=1.0).contains(&longest));
+        assert!(shortest <= longest);
+        self.shortest_daylight = shortest;
+        self.longest_daylight = longest;
+    }
+}
+
+/// Where a moment falls in the in-game year. Obtained from `Calendar::season`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeasonTime {
+    /// Number of in-game years elapsed. The first year is year 0.
+    pub year: u64,
+    /// Index of the season in the year, from 0 to `Seasons::count` excluded.
+    pub season: u32,
+    /// How far into the season the moment is, from 0 to 1.
+    pub progress: f64,
+}
+
+/// Maps the scaled absolute time of a `Time` onto in-game days, hours and minutes.
+///
+/// Named cycles, like an 8-day moon cycle, can be layered on top of the days with
+/// `add_cycle`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calendar {
+    /// Scaled game time taken by one in-game day.
+    day_length: Duration,
+    seasons: Option<Seasons>,
+    /// Scaled game time taken by each named cycle, in nanoseconds.
+    cycles: BTreeMap<String, u128>,
+}
+
+impl Calendar {
+    /// Creates a calendar where an in-game day lasts `day_length` of scaled game time.
+    ///
+    /// ## Panics
+    /// This will panic if day_length is zero.
+    pub fn new(day_length: Duration) -> Self {
+        assert!(day_length > Duration::from_secs(0));
+        Calendar {
+            day_length,
+            seasons: None,
+            cycles: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the scaled game time taken by one in-game day.
+    pub fn day_length(&self) -> Duration {
+        self.day_length
+    }
+
+    /// Gets the in-game date and time at the given scaled absolute time.
+    pub fn date_time(&self, absolute_time: Duration) -> GameDateTime {
+        let day_length = self.day_length.as_nanos();
+        let nanos = absolute_time.as_nanos();
+        let seconds = (nanos % day_length) * SECONDS_PER_DAY / day_length;
+        let seconds = seconds as u32;
+        GameDateTime {
+            day: (nanos / day_length) as u64,
+            hour: seconds / (MINUTES_PER_HOUR * SECONDS_PER_MINUTE),
+            minute: seconds / SECONDS_PER_MINUTE % MINUTES_PER_HOUR,
+            second: seconds % SECONDS_PER_MINUTE,
+        }
+    }
+
+    /// Gets the in-game date and time of the clock.
+    pub fn now(&self, time: &Time) -> GameDateTime {
+        self.date_time(time.absolute_time())
+    }
+
+    /// Gets how far into the current in-game day the given time is, from 0 to 1.
+    pub fn time_of_day(&self, absolute_time: Duration) -> f64 {
+        let day_length = self.day_length.as_nanos();
+        (absolute_time.as_nanos() % day_length) as f64 / day_length as f64
+    }
+
+    /// Gets the seasons of the year, if any.
+    pub fn seasons(&self) -> Option<&Seasons> {
+        self.seasons.as_ref()
+    }
+
+    /// Sets the seasons of the year. Without seasons, days and nights last as long all year.
+    pub fn set_seasons(&mut self, seasons: Option<Seasons>) {
+        self.seasons = seasons;
+    }
+
+    /// Gets the scaled game time taken by one in-game season, in nanoseconds.
+    fn season_length(&self, seasons: &Seasons) -> u128 {
+        self.day_length.as_nanos() * u128::from(seasons.days_per_year) / u128::from(seasons.count)
+    }
+
+    /// Gets the year, season and season progress at the given scaled absolute time,
+    /// or None if the calendar has no seasons.
+    pub fn season(&self, absolute_time: Duration) -> Option<SeasonTime> {
+        let seasons = self.seasons?;
+        let season_length = self.season_length(&seasons);
+        let index = absolute_time.as_nanos() / season_length;
+        Some(SeasonTime {
+            year: (index / u128::from(seasons.count)) as u64,
+            season: (index % u128::from(seasons.count)) as u32,
+            progress: (absolute_time.as_nanos() % season_length) as f64 / season_length as f64,
+        })
+    }
+
+    /// Gets the fraction of the in-game day containing the given time that is daylight.
+    /// It follows a cosine curve from the shortest day, at the start of the year, to the
+    /// longest day, in the middle of the year. Without seasons, it is always 0.5.
+    pub fn daylight(&self, absolute_time: Duration) -> f64 {
+        match self.seasons {
+            Some(seasons) => {
+                let day = (absolute_time.as_nanos() / self.day_length.as_nanos())
+                    % u128::from(seasons.days_per_year);
+                let year_progress = day as f64 / f64::from(seasons.days_per_year);
+                let curve = (1.0 - (2.0 * std::f64::consts::PI * year_progress).cos()) / 2.0;
+                seasons.shortest_daylight
+                    + (seasons.longest_daylight - seasons.shortest_daylight) * curve
+            }
+            None => 0.5,
+        }
+    }
+
+    /// Gets the time of day of the sunrise, from 0 to 1, on the in-game day containing the
+    /// given time. Days are centered on noon.
+    pub fn sunrise(&self, absolute_time: Duration) -> f64 {
+        0.5 - self.daylight(absolute_time) / 2.0
+    }
+
+    /// Gets the time of day of the sunset, from 0 to 1, on the in-game day containing the
+    /// given time. Days are centered on noon.
+    pub fn sunset(&self, absolute_time: Duration) -> f64 {
+        0.5 + self.daylight(absolute_time) / 2.0
+    }
+
+    /// Adds a cycle lasting `days` in-game days, starting at time zero, replacing any cycle
+    /// with the same name.
+    ///
+    /// ## Panics
+    /// This will panic if days is NaN, Infinity, or too small to last a nanosecond.
+    pub fn add_cycle(&mut self, name: impl Into<String>, days: f64) {
+        assert!(days.is_finite());
+        let period = self.day_length.as_nanos() as f64 * days;
+        assert!(period >= 1.0);
+        self.cycles.insert(name.into(), period as u128);
+    }
+
+    /// Removes the named cycle. Returns false if there was none.
+    pub fn remove_cycle(&mut self, name: &str) -> bool {
+        self.cycles.remove(name).is_some()
+    }
+
+    /// Gets the scaled game time taken by the named cycle.
+    pub fn cycle_length(&self, name: &str) -> Option<Duration> {
+        self.cycles.get(name).map(|&period| {
+            Duration::new(
+                (period / 1_000_000_000) as u64,
+                (period % 1_000_000_000) as u32,
+            )
+        })
+    }
+
+    /// Gets how far into the current occurrence of the named cycle the given time is,
+    /// from 0 to 1, or None if there is no such cycle.
+    pub fn phase_of(&self, name: &str, absolute_time: Duration) -> Option<f64> {
+        let period = *self.cycles.get(name)?;
+        Some((absolute_time.as_nanos() % period) as f64 / period as f64)
+    }
+
+    /// Gets the number of complete occurrences of the named cycle at the given time,
+    /// or None if there is no such cycle.
+    pub fn cycles_elapsed(&self, name: &str, absolute_time: Duration) -> Option<u64> {
+        let period = *self.cycles.get(name)?;
+        Some((absolute_time.as_nanos() / period) as u64)
+    }
+
+    /// Checks if the sun is up at the given time.
+    pub fn is_daytime(&self, absolute_time: Duration) -> bool {
+        let time_of_day = self.time_of_day(absolute_time);
+        (self.sunrise(absolute_time)..self.sunset(absolute_time)).contains(&time_of_day)
+    }
+}
+
+/// A recurring moment expressed in in-game calendar terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarSchedule {
+    /// Every in-game day at the given hour and minute.
+    Daily {
+        /// Hour of the day, from 0 to 23.
+        hour: u32,
+        /// Minute of the hour, from 0 to 59.
+        minute: u32,
+    },
+    /// Every `n` in-game hours, on the hour, starting at midnight.
+    EveryHours(u32),
+    /// At the start of every season after the first one, since the clock starts inside it.
+    SeasonStart,
+    /// At the start of every occurrence of the named cycle. See `Calendar::add_cycle`.
+    CycleStart(&'static str),
+}
+
+impl CalendarSchedule {
+    /// Gets the period and the offset from time zero of the schedule, in nanoseconds.
+    fn period_and_phase(&self, calendar: &Calendar) -> (u128, u128) {
+        let day_length = calendar.day_length.as_nanos();
+        match *self {
+            CalendarSchedule::Daily { hour, minute } => {
+                let minute_of_day = u128::from(hour * MINUTES_PER_HOUR + minute);
+                let minutes_per_day = u128::from(HOURS_PER_DAY * MINUTES_PER_HOUR);
+                (day_length, day_length * minute_of_day / minutes_per_day)
+            }
+            CalendarSchedule::EveryHours(hours) => (
+                day_length * u128::from(hours) / u128::from(HOURS_PER_DAY),
+                0,
+            ),
+            CalendarSchedule::SeasonStart => match calendar.seasons {
+                Some(seasons) => (calendar.season_length(&seasons), 0),
+                None => (0, 0),
+            },
+            CalendarSchedule::CycleStart(name) => {
+                (calendar.cycles.get(name).copied().unwrap_or(0), 0)
+            }
+        }
+    }
+
+    /// Panics if the schedule is invalid on the given calendar.
+    fn validate(&self, calendar: &Calendar) {
+        match *self {
+            CalendarSchedule::Daily { hour, minute } => assert!(
+                hour < HOURS_PER_DAY && minute < MINUTES_PER_HOUR,
+                "hour or minute out of range"
+            ),
+            CalendarSchedule::EveryHours(hours) => assert!(hours > 0, "zero hours"),
+            CalendarSchedule::SeasonStart => {
+                assert!(calendar.seasons.is_some(), "calendar without seasons")
+            }
+            CalendarSchedule::CycleStart(name) => assert!(
+                calendar.cycles.contains_key(name),
+                "cycle missing from the calendar"
+            ),
+        }
+    }
+
+    /// Gets the scaled absolute times at which the schedule fires in `(from, to]`.
+    ///
+    /// ## Panics
+    /// This will panic if the schedule is invalid (an hour or minute out of range, zero hours,
+    /// season starts on a calendar without seasons, or a cycle missing from the calendar).
+    pub fn occurrences(
+        &self,
+        calendar: &Calendar,
+        from: Duration,
+        to: Duration,
+    ) -> impl Iterator<Item = Duration> {
+        self.validate(calendar);
+        let (period, phase) = self.period_and_phase(calendar);
+        let period = period.max(1);
+        let from = from.as_nanos();
+        let to = to.as_nanos();
+        // Index of the first occurrence strictly after `from`.
+        let first = if from < phase {
+            0
+        } else {
+            (from - phase) / period + 1
+        };
+        (first..)
+            .map(move |index| phase + index * period)
+            .take_while(move |nanos| *nanos <= to)
+            .map(|nanos| {
+                Duration::new(
+                    (nanos / 1_000_000_000) as u64,
+                    (nanos % 1_000_000_000) as u32,
+                )
+            })
+    }
+}
+
+/// Fires user events when scaled game time crosses calendar boundaries.
+///
+/// Because it follows `Time::absolute_time`, pausing the game delays the events and
+/// speeding it up fires them sooner. When a single frame crosses several boundaries,
+/// every occurrence is reported, in chronological order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalendarEvents<E> {
+    calendar: Calendar,
+    schedules: Vec<(CalendarSchedule, E)>,
+    /// Scaled absolute time of the last update.
+    last_time: Duration,
+}
+
+impl<E: Clone> CalendarEvents<E> {
+    /// Creates an empty set of calendar events starting at the current time of the clock.
+    pub fn new(calendar: Calendar, time: &Time) -> Self {
+        CalendarEvents {
+            calendar,
+            schedules: vec![],
+            last_time: time.absolute_time(),
+        }
+    }
+
+    /// Gets the calendar used to resolve the schedules.
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    /// Adds a schedule that yields `event` every time it fires.
+    ///
+    /// ## Panics
+    /// This will panic if the schedule is invalid (an hour or minute out of range, zero hours,
+    /// season starts on a calendar without seasons, or a cycle missing from the calendar).
+    pub fn add(&mut self, schedule: CalendarSchedule, event: E) {
+        schedule.validate(&self.calendar);
+        self.schedules.push((schedule, event));
+    }
+
+    /// Gets the events whose schedules fired since the last update.
+    pub fn update(&mut self, time: &Time) -> Vec<E> {
+        let now = time.absolute_time();
+        let mut fired = vec![];
+        for (index, (schedule, _)) in self.schedules.iter().enumerate() {
+            for at in schedule.occurrences(&self.calendar, self.last_time, now) {
+                fired.push((at, index));
+            }
+        }
+        self.last_time = now;
+        fired.sort();
+        fired
+            .into_iter()
+            .map(|(_, index)| self.schedules[index].1.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn date_time_from_absolute_time() {
+        // One in-game day lasts 24 real seconds, so an in-game hour is one second.
+        let calendar = Calendar::new(Duration::from_secs(24));
+        let at = calendar.date_time(Duration::from_millis(24_000 + 6_500));
+        assert_eq!(
+            at,
+            GameDateTime {
+                day: 1,
+                hour: 6,
+                minute: 30,
+                second: 0
+            }
+        );
+    }
+
+    #[test]
+    fn events_follow_scaled_time() {
+        let mut time = Time::default();
+        let calendar = Calendar::new(Duration::from_secs(24));
+        let mut events = CalendarEvents::new(calendar, &time);
+        events.add(CalendarSchedule::Daily { hour: 6, minute: 0 }, "dawn");
+        events.add(CalendarSchedule::EveryHours(3), "tick");
+
+        time.advance_frame(Duration::from_millis(6_500));
+        assert_eq!(events.update(&time), vec!["tick", "dawn", "tick"]);
+
+        // Paused: no boundary is crossed.
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(10));
+        assert!(events.update(&time).is_empty());
+
+        // Double speed: 3 real seconds cover 6 in-game hours.
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_secs(3));
+        assert_eq!(events.update(&time), vec!["tick", "tick"]);
+    }
+
+    #[test]
+    fn seasons_and_daylight() {
+        // One in-game day lasts 24 real seconds, and a year of 8 days has 4 seasons.
+        let mut calendar = Calendar::new(Duration::from_secs(24));
+        assert_eq!(calendar.daylight(Duration::from_secs(0)), 0.5);
+        assert_eq!(calendar.season(Duration::from_secs(0)), None);
+        calendar.set_seasons(Some(Seasons::new(8, 4)));
+
+        assert_eq!(
+            calendar.season(Duration::from_secs(24 * 11 + 12)),
+            Some(SeasonTime {
+                year: 1,
+                season: 1,
+                progress: 0.75
+            })
+        );
+        // Shortest day at the start of the year, longest day in the middle.
+        assert!((calendar.daylight(Duration::from_secs(0)) - 0.35).abs() < 1e-9);
+        assert!((calendar.daylight(Duration::from_secs(24 * 4)) - 0.65).abs() < 1e-9);
+        assert!((calendar.sunrise(Duration::from_secs(24 * 4)) - 0.175).abs() < 1e-9);
+        // 7:00 is before sunrise in winter and after sunrise in summer.
+        assert!(!calendar.is_daytime(Duration::from_secs(7)));
+        assert!(calendar.is_daytime(Duration::from_secs(24 * 4 + 7)));
+
+        let mut time = Time::default();
+        let mut events = CalendarEvents::new(calendar, &time);
+        events.add(CalendarSchedule::SeasonStart, "season");
+        time.advance_frame(Duration::from_secs(24 * 5));
+        assert_eq!(events.update(&time), vec!["season", "season"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle missing from the calendar")]
+    fn invalid_schedule_rejected_when_added() {
+        let calendar = Calendar::new(Duration::from_secs(24));
+        let mut events = CalendarEvents::new(calendar, &Time::default());
+        events.add(CalendarSchedule::CycleStart("moon"), "new moon");
+    }
+
+    #[test]
+    fn named_cycles() {
+        let mut calendar = Calendar::new(Duration::from_secs(24));
+        calendar.add_cycle("moon", 8.0);
+        calendar.add_cycle("tide", 0.5);
+        let at = Duration::from_secs(24 * 10);
+        assert_eq!(calendar.phase_of("moon", at), Some(0.25));
+        assert_eq!(calendar.cycles_elapsed("moon", at), Some(1));
+        assert_eq!(
+            calendar.phase_of("tide", at + Duration::from_secs(3)),
+            Some(0.25)
+        );
+        assert_eq!(calendar.cycle_length("tide"), Some(Duration::from_secs(12)));
+        assert_eq!(calendar.phase_of("sun", at), None);
+
+        let mut time = Time::default();
+        let mut events = CalendarEvents::new(calendar, &time);
+        events.add(CalendarSchedule::CycleStart("moon"), "new moon");
+        time.advance_frame(Duration::from_secs(24 * 8 - 1));
+        assert!(events.update(&time).is_empty());
+        time.advance_frame(Duration::from_secs(1));
+        assert_eq!(events.update(&time), vec!["new moon"]);
+    }
+}
@@ -0,0 +1,186 @@
+//! Several clocks advanced together from one measured delta.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::Time;
+
+/// How far one clock of a `ClockSet` lags behind the most advanced one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClockDrift {
+    /// Name of the clock.
+    pub name: String,
+    /// Real absolute time of the clock.
+    pub absolute_real_time: Duration,
+    /// Real time the clock is behind the most advanced clock.
+    pub behind: Duration,
+    /// Number of frames the clock is behind the clock with the most frames.
+    pub frames_behind: u64,
+    /// True if the clock is behind by more than the tolerance.
+    pub diverged: bool,
+}
+
+/// Comparison of the real absolute times of the clocks of a `ClockSet`.
+/// Obtained from `ClockSet::drift_report`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DriftReport {
+    /// Every clock, ordered by name.
+    pub clocks: Vec<ClockDrift>,
+}
+
+impl DriftReport {
+    /// Checks if every clock is within the tolerance of the most advanced one.
+    pub fn is_synchronized(&self) -> bool {
+        self.clocks.iter().all(|clock| !clock.diverged)
+    }
+
+    /// Gets the clocks behind by more than the tolerance.
+    pub fn diverged(&self) -> impl Iterator<Item = &ClockDrift> {
+        self.clocks.iter().filter(|clock| clock.diverged)
+    }
+}
+
+/// Named clocks (world, UI, background simulation...) advanced in a single call.
+///
+/// Each clock keeps its own time scale, scale layers and fixed time step, but they all receive
+/// the same real delta, so they can't drift apart by being advanced at slightly different
+/// points of the frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClockSet {
+    clocks: BTreeMap<String, Time>,
+}
+
+impl ClockSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        ClockSet::default()
+    }
+
+    /// Adds a clock under the given name, returning the clock it replaced.
+    pub fn insert(&mut self, name: impl Into<String>, time: Time) -> Option<Time> {
+        self.clocks.insert(name.into(), time)
+    }
+
+    /// Removes the named clock.
+    pub fn remove(&mut self, name: &str) -> Option<Time> {
+        self.clocks.remove(name)
+    }
+
+    /// Gets the named clock.
+    pub fn get(&self, name: &str) -> Option<&Time> {
+        self.clocks.get(name)
+    }
+
+    /// Gets the named clock mutably, for example to change its time scale.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Time> {
+        self.clocks.get_mut(name)
+    }
+
+    /// Gets the number of clocks.
+    pub fn len(&self) -> usize {
+        self.clocks.len()
+    }
+
+    /// Checks if the set has no clocks.
+    pub fn is_empty(&self) -> bool {
+        self.clocks.is_empty()
+    }
+
+    /// Iterates over the clocks, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Time)> {
+        self.clocks.iter().map(|(name, time)| (name.as_str(), time))
+    }
+
+    /// Iterates mutably over the clocks, ordered by name.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Time)> {
+        self.clocks
+            .iter_mut()
+            .map(|(name, time)| (name.as_str(), time))
+    }
+
+    /// Compares the real absolute times of the clocks, flagging those behind the most
+    /// advanced one by more than `tolerance`. A clock that missed an `advance_frame` call,
+    /// for example through `get_mut`, otherwise only shows up as weird gameplay.
+    pub fn drift_report(&self, tolerance: Duration) -> DriftReport {
+        let latest = self.clocks.values().map(Time::absolute_real_time).max();
+        let most_frames = self.clocks.values().map(Time::frame_number).max();
+        let clocks = self
+            .clocks
+            .iter()
+            .map(|(name, time)| {
+                let behind = latest.unwrap_or_default() - time.absolute_real_time();
+                ClockDrift {
+                    name: name.clone(),
+                    absolute_real_time: time.absolute_real_time(),
+                    behind,
+                    frames_behind: most_frames.unwrap_or_default() - time.frame_number(),
+                    diverged: behind > tolerance,
+                }
+            })
+            .collect();
+        DriftReport { clocks }
+    }
+
+    /// Advances every clock by the same real delta. See `Time::advance_frame`.
+    pub fn advance_all(&mut self, real_delta: Duration) {
+        for time in self.clocks.values_mut() {
+            time.advance_frame(real_delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn clocks_share_real_delta() {
+        let mut clocks = ClockSet::new();
+        clocks.insert("world", Time::default());
+        clocks.insert("ui", Time::default());
+        clocks.get_mut("world").unwrap().set_time_scale(0.0);
+
+        for _ in 0..10 {
+            clocks.advance_all(Duration::from_millis(16));
+        }
+        let world = clocks.get("world").unwrap();
+        let ui = clocks.get("ui").unwrap();
+        assert_eq!(world.absolute_real_time(), ui.absolute_real_time());
+        assert_eq!(world.absolute_time(), Duration::from_secs(0));
+        assert_eq!(ui.absolute_time(), Duration::from_millis(160));
+        assert_eq!(
+            clocks.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            vec!["ui", "world"]
+        );
+    }
+
+    #[test]
+    fn drift_report_flags_missed_advance() {
+        let mut clocks = ClockSet::new();
+        clocks.insert("world", Time::default());
+        clocks.insert("ui", Time::default());
+        clocks.advance_all(Duration::from_millis(16));
+        assert!(clocks
+            .drift_report(Duration::from_secs(0))
+            .is_synchronized());
+
+        clocks
+            .get_mut("ui")
+            .unwrap()
+            .advance_frame(Duration::from_millis(16));
+        clocks
+            .get_mut("ui")
+            .unwrap()
+            .advance_frame(Duration::from_millis(16));
+        let report = clocks.drift_report(Duration::from_millis(20));
+        let diverged = report.diverged().collect::<Vec<_>>();
+        assert_eq!(diverged.len(), 1);
+        assert_eq!(diverged[0].name, "world");
+        assert_eq!(diverged[0].behind, Duration::from_millis(32));
+        assert_eq!(diverged[0].frames_behind, 2);
+        assert!(clocks
+            .drift_report(Duration::from_millis(40))
+            .is_synchronized());
+    }
+}
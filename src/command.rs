@@ -0,0 +1,140 @@
+//! Commands stamped with the fixed tick that must execute them.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Time;
+
+/// A command pushed for a fixed tick that already ran. Returned by `CommandQueue::push`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LateCommand<T> {
+    /// Tick the command was stamped with.
+    pub tick: u64,
+    /// Last tick taken from the queue when the command was pushed.
+    pub executed_tick: u64,
+    /// The command.
+    pub command: T,
+}
+
+impl<T> fmt::Display for LateCommand<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "command for tick {} arrived after tick {} ran",
+            self.tick, self.executed_tick
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for LateCommand<T> {}
+
+/// Commands keyed by the fixed tick that must execute them, numbered like
+/// `Time::fixed_step_count`, for the deterministic input handling of lockstep and rollback
+/// games.
+///
+/// Push commands as they arrive, local or remote, and call `update` once in every fixed step:
+/// it yields exactly the commands stamped with the tick being run. Commands for a tick that
+/// already ran are rejected as late, so the game can roll back or drop them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandQueue<T> {
+    /// Pending commands by tick, in push order.
+    pending: BTreeMap<u64, Vec<T>>,
+    /// Last tick taken by `update`, 0 before the first one.
+    executed_tick: u64,
+}
+
+impl<T> CommandQueue<T> {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        CommandQueue {
+            pending: BTreeMap::new(),
+            executed_tick: 0,
+        }
+    }
+
+    /// Gets the last tick taken by `update`, or 0 before the first one.
+    pub fn executed_tick(&self) -> u64 {
+        self.executed_tick
+    }
+
+    /// Gets the number of pending commands.
+    pub fn len(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Checks if there are no pending commands.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Adds a command for the given tick. Commands for the same tick are yielded in the order
+    /// they were pushed.
+    pub fn push(&mut self, tick: u64, command: T) -> Result<(), LateCommand<T>> {
+        if tick <= self.executed_tick {
+            return Err(LateCommand {
+                tick,
+                executed_tick: self.executed_tick,
+                command,
+            });
+        }
+        self.pending.entry(tick).or_default().push(command);
+        Ok(())
+    }
+
+    /// Takes the commands for the fixed tick being run. Call it once in every fixed step,
+    /// after `Time::step_fixed_update` returned true.
+    ///
+    /// Commands of ticks skipped by not calling `update` are yielded first, in tick order.
+    /// In strict mode, this panics instead.
+    pub fn update(&mut self, time: &Time) -> Vec<T> {
+        let tick = time.fixed_step_count();
+        let later = self.pending.split_off(&(tick + 1));
+        let due = std::mem::replace(&mut self.pending, later);
+        crate::strict_assert(
+            due.keys().all(|due_tick| *due_tick == tick),
+            "CommandQueue::update was not called for every tick",
+        );
+        self.executed_tick = self.executed_tick.max(tick);
+        due.into_values().flatten().collect()
+    }
+}
+
+impl<T> Default for CommandQueue<T> {
+    fn default() -> Self {
+        CommandQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn commands_run_at_their_tick() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        let mut queue = CommandQueue::new();
+        queue.push(2, "jump").unwrap();
+        queue.push(1, "left").unwrap();
+        queue.push(2, "fire").unwrap();
+        queue.push(5, "right").unwrap();
+        assert_eq!(queue.len(), 4);
+
+        let mut ran = vec![];
+        time.advance_frame(Duration::from_millis(30));
+        while time.step_fixed_update() {
+            ran.push(queue.update(&time));
+        }
+        assert_eq!(ran, vec![vec!["left"], vec!["jump", "fire"], vec![]]);
+        assert_eq!(queue.executed_tick(), 3);
+
+        let late = queue.push(3, "crouch").unwrap_err();
+        assert_eq!(late.command, "crouch");
+        assert_eq!(
+            late.to_string(),
+            "command for tick 3 arrived after tick 3 ran"
+        );
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Time counting down to zero, for match timers and bomb timers.
+
+use std::time::Duration;
+
+use crate::{Time, TimerMode};
+
+/// Counts down from a duration to zero, following scaled or real time.
+///
+/// Call `update` once per frame, then check `just_expired`.
+/// Time can be added and removed while counting, for "+30 seconds" pickups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Countdown {
+    remaining: Duration,
+    mode: TimerMode,
+    /// True if the countdown reached zero during the last update.
+    just_expired: bool,
+    /// True once reaching zero has been reported.
+    expired: bool,
+}
+
+impl Countdown {
+    /// Creates a countdown of `duration`, ticking down with scaled game time, or real time in
+    /// `TimerMode::RealTime`.
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Countdown {
+            remaining: duration,
+            mode,
+            just_expired: false,
+            expired: false,
+        }
+    }
+
+    /// Gets the time left.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// Gets the timeline the countdown follows.
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    /// Adds time to the countdown. A countdown given time after expiring expires again when
+    /// it reaches zero.
+    pub fn add_time(&mut self, time: Duration) {
+        self.remaining += time;
+        if self.remaining > Duration::from_secs(0) {
+            self.expired = false;
+        }
+    }
+
+    /// Removes time from the countdown, stopping at zero. Reaching zero this way is reported
+    /// by the next `update`.
+    pub fn remove_time(&mut self, time: Duration) {
+        self.remaining = self.remaining.saturating_sub(time);
+    }
+
+    /// Counts down by the delta of the frame.
+    pub fn update(&mut self, time: &Time) {
+        let delta = match self.mode {
+            TimerMode::GameTime => time.delta_time(),
+            TimerMode::RealTime => time.delta_real_time(),
+        };
+        self.remaining = self.remaining.saturating_sub(delta);
+        let zero = self.remaining == Duration::from_secs(0);
+        self.just_expired = zero && !self.expired;
+        self.expired = zero;
+    }
+
+    /// Checks if the countdown reached zero during the last update.
+    pub fn just_expired(&self) -> bool {
+        self.just_expired
+    }
+
+    /// Checks if no time is left.
+    pub fn is_expired(&self) -> bool {
+        self.remaining == Duration::from_secs(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn match_timer() {
+        let mut time = Time::default();
+        let mut countdown = Countdown::new(Duration::from_secs(2), TimerMode::GameTime);
+        let mut expirations = 0;
+        let mut frame = |countdown: &mut Countdown, time: &mut Time| {
+            time.advance_frame(Duration::from_millis(500));
+            countdown.update(time);
+            expirations += countdown.just_expired() as u32;
+        };
+        for _ in 0..3 {
+            frame(&mut countdown, &mut time);
+        }
+        countdown.add_time(Duration::from_secs(1));
+        assert_eq!(countdown.remaining(), Duration::from_millis(1_500));
+        for _ in 0..5 {
+            frame(&mut countdown, &mut time);
+        }
+        assert!(countdown.is_expired());
+        countdown.add_time(Duration::from_secs(1));
+        countdown.remove_time(Duration::from_secs(30));
+        frame(&mut countdown, &mut time);
+        assert_eq!(expirations, 2);
+    }
+}
@@ -0,0 +1,193 @@
+//! Keyframed curves for simple property animation.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// How a `Curve` interpolates between two keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CurveInterpolation {
+    /// Straight lines between the keys.
+    #[default]
+    Linear,
+    /// Cubic Hermite splines, following the tangents of the keys.
+    CubicHermite,
+}
+
+/// What a `Curve` does past its last key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LoopMode {
+    /// Keeps the value of the last key.
+    #[default]
+    Once,
+    /// Starts over from the first key.
+    Loop,
+    /// Plays backward to the first key, then forward again.
+    PingPong,
+}
+
+/// A value reached at a given time of a `Curve`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurveKey {
+    /// Time of the key.
+    pub time: Duration,
+    /// Value at `time`.
+    pub value: f32,
+    /// Slope at `time`, in value per second. Only used by `CurveInterpolation::CubicHermite`.
+    pub tangent: f32,
+}
+
+/// A value changing over time, defined by keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Curve {
+    /// Keys ordered by time.
+    keys: Vec<CurveKey>,
+    interpolation: CurveInterpolation,
+    loop_mode: LoopMode,
+}
+
+impl Curve {
+    /// Creates a curve without keys, which is always 0.
+    pub fn new(interpolation: CurveInterpolation, loop_mode: LoopMode) -> Self {
+        Curve {
+            keys: vec![],
+            interpolation,
+            loop_mode,
+        }
+    }
+
+    /// Adds a key with a flat tangent.
+    pub fn add_key(&mut self, time: Duration, value: f32) {
+        self.add_key_with_tangent(time, value, 0.0);
+    }
+
+    /// Adds a key with the given slope, in value per second.
+    /// A key added at the time of an existing key goes after it.
+    pub fn add_key_with_tangent(&mut self, time: Duration, value: f32, tangent: f32) {
+        let index = self.keys.partition_point(|key| key.time <= time);
+        self.keys.insert(
+            index,
+            CurveKey {
+                time,
+                value,
+                tangent,
+            },
+        );
+    }
+
+    /// Gets the keys, ordered by time.
+    pub fn keys(&self) -> &[CurveKey] {
+        &self.keys
+    }
+
+    /// Gets how the curve interpolates between two keys.
+    pub fn interpolation(&self) -> CurveInterpolation {
+        self.interpolation
+    }
+
+    /// Gets what the curve does past its last key.
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    /// Gets the value of the curve at the scaled absolute time of the clock.
+    pub fn sample_at(&self, time: &Time) -> f32 {
+        self.sample(time.absolute_time())
+    }
+
+    /// Gets the value of the curve at the given time. Before the first key, this is the value
+    /// of the first key.
+    pub fn sample(&self, time: Duration) -> f32 {
+        let (first, last) = match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first.time, last.time),
+            _ => return 0.0,
+        };
+        let span = (last - first).as_nanos();
+        let time = if time <= last || span == 0 {
+            time
+        } else {
+            let since_first = (time - first).as_nanos();
+            let offset = match self.loop_mode {
+                LoopMode::Once => span,
+                LoopMode::Loop => since_first % span,
+                LoopMode::PingPong => {
+                    let offset = since_first % (2 * span);
+                    if offset > span {
+                        2 * span - offset
+                    } else {
+                        offset
+                    }
+                }
+            };
+            first + Duration::from_nanos(offset as u64)
+        };
+
+        let next = self.keys.partition_point(|key| key.time <= time);
+        if next == 0 {
+            return self.keys[0].value;
+        }
+        let previous = self.keys[next - 1];
+        let next = match self.keys.get(next) {
+            Some(next) => *next,
+            None => return previous.value,
+        };
+        let length = (next.time - previous.time).as_secs_f32();
+        let t = (time - previous.time).as_secs_f32() / length;
+        match self.interpolation {
+            CurveInterpolation::Linear => previous.value + (next.value - previous.value) * t,
+            CurveInterpolation::CubicHermite => {
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (2.0 * t3 - 3.0 * t2 + 1.0) * previous.value
+                    + (t3 - 2.0 * t2 + t) * previous.tangent * length
+                    + (-2.0 * t3 + 3.0 * t2) * next.value
+                    + (t3 - t2) * next.tangent * length
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn linear_looping_modes() {
+        let mut curve = Curve::new(CurveInterpolation::Linear, LoopMode::Once);
+        curve.add_key(Duration::from_secs(0), 0.0);
+        curve.add_key(Duration::from_secs(2), 10.0);
+        assert_eq!(curve.sample(Duration::from_secs(1)), 5.0);
+        assert_eq!(curve.sample(Duration::from_secs(3)), 10.0);
+
+        let mut looping = Curve::new(CurveInterpolation::Linear, LoopMode::Loop);
+        let mut ping_pong = Curve::new(CurveInterpolation::Linear, LoopMode::PingPong);
+        for key in curve.keys() {
+            looping.add_key(key.time, key.value);
+            ping_pong.add_key(key.time, key.value);
+        }
+        assert_eq!(looping.sample(Duration::from_millis(2_500)), 2.5);
+        assert_eq!(ping_pong.sample(Duration::from_millis(2_500)), 7.5);
+        assert_eq!(ping_pong.sample(Duration::from_millis(4_500)), 2.5);
+
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(250));
+        assert_eq!(curve.sample_at(&time), 2.5);
+    }
+
+    #[test]
+    fn cubic_hermite() {
+        let mut curve = Curve::new(CurveInterpolation::CubicHermite, LoopMode::Once);
+        curve.add_key(Duration::from_secs(0), 0.0);
+        curve.add_key(Duration::from_secs(1), 1.0);
+        // Flat tangents ease in and out.
+        assert_eq!(curve.sample(Duration::from_millis(500)), 0.5);
+        assert!(curve.sample(Duration::from_millis(250)) < 0.25);
+
+        let mut straight = Curve::new(CurveInterpolation::CubicHermite, LoopMode::Once);
+        straight.add_key_with_tangent(Duration::from_secs(0), 0.0, 1.0);
+        straight.add_key_with_tangent(Duration::from_secs(1), 1.0, 1.0);
+        assert!((straight.sample(Duration::from_millis(250)) - 0.25).abs() < 1e-6);
+    }
+}
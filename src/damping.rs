@@ -0,0 +1,71 @@
+//! Frame rate independent exponential smoothing.
+//!
+//! The usual `lerp(current, target, k * delta)` moves faster at low frame rates, and
+//! overshoots when `k * delta` exceeds 1. These helpers use the exponential decay formulation
+//! instead, which gives the same result whatever the frame rate.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// Gets the fraction of the remaining distance covered in `delta` when decaying at `lambda`
+/// per second.
+pub fn damp_factor(lambda: f32, delta: Duration) -> f32 {
+    1.0 - (-lambda * delta.as_secs_f32()).exp()
+}
+
+/// Moves `current` toward `target`, covering the fraction `1 - e^(-lambda * delta)` of the
+/// distance, where delta is the scaled delta time of the frame. Higher values of `lambda`
+/// converge faster.
+pub fn damp(current: f32, target: f32, lambda: f32, time: &Time) -> f32 {
+    current + (target - current) * damp_factor(lambda, time.delta_time())
+}
+
+/// Moves `current` toward `target`, so that only the fraction `remaining` of the distance is
+/// left after one second of scaled game time. For example, 0.01 covers 99% of the distance
+/// every second.
+///
+/// ## Panics
+/// This will panic if remaining is not between 0 and 1.
+pub fn smooth_lerp(current: f32, target: f32, remaining: f32, time: &Time) -> f32 {
+    assert!((0.0..=1.0).contains(&remaining));
+    let t = 1.0 - remaining.powf(time.delta_time().as_secs_f32());
+    current + (target - current) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    fn run(fps: u32, step: impl Fn(f32, &Time) -> f32) -> f32 {
+        let mut time = Time::default();
+        let mut value = 0.0;
+        for _ in 0..fps {
+            time.advance_frame(Duration::from_secs(1) / fps);
+            value = step(value, &time);
+        }
+        value
+    }
+
+    #[test]
+    fn independent_of_frame_rate() {
+        let damp_30 = run(30, |value, time| damp(value, 10.0, 2.0, time));
+        let damp_240 = run(240, |value, time| damp(value, 10.0, 2.0, time));
+        assert!((damp_30 - damp_240).abs() < 1e-3);
+        assert!((damp_30 - 10.0 * (1.0 - (-2.0f32).exp())).abs() < 1e-3);
+
+        let lerp_30 = run(30, |value, time| smooth_lerp(value, 1.0, 0.01, time));
+        let lerp_240 = run(240, |value, time| smooth_lerp(value, 1.0, 0.01, time));
+        assert!((lerp_30 - 0.99).abs() < 1e-3);
+        assert!((lerp_240 - 0.99).abs() < 1e-3);
+    }
+
+    #[test]
+    fn paused_does_not_move() {
+        let mut time = Time::default();
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_millis(16));
+        assert_eq!(damp(1.0, 5.0, 10.0, &time), 1.0);
+    }
+}
@@ -0,0 +1,88 @@
+//! Aggregated timing data for on-screen debug overlays and telemetry.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// A snapshot of the values of a clock worth displaying on screen.
+///
+/// Obtained from `Time::debug_snapshot`. Send it to remote telemetry tools with `to_bytes`,
+/// whose layout only ever grows at the end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DebugSnapshot {
+    /// Number of the frame.
+    pub frame_number: u64,
+    /// Frames per second, computed from the real delta of this frame. Zero before the first frame.
+    pub fps: f64,
+    /// Time elapsed since the previous frame.
+    pub delta_time: Duration,
+    /// Time elapsed since the previous frame ignoring the time speed multiplier.
+    pub delta_real_time: Duration,
+    /// Current time speed multiplier, including every scale layer.
+    pub time_scale: f64,
+    /// Time waiting in the fixed step accumulator, as a fraction of the fixed time step.
+    /// Values above 1 mean fixed steps are still due.
+    pub accumulator_fill: f64,
+    /// Number of fixed steps that ran this frame.
+    pub fixed_steps: u32,
+    /// True if this frame took longer than the hitch threshold of the clock.
+    pub hitch: bool,
+}
+
+impl DebugSnapshot {
+    /// Length of the encoding produced by `to_bytes`.
+    pub const ENCODED_LEN: usize = 53;
+
+    /// Encodes the snapshot in a fixed little-endian layout, the same on every platform:
+    /// `frame_number`, `fps`, `delta_time` and `delta_real_time` in nanoseconds,
+    /// `time_scale` and `accumulator_fill` as 8 bytes each, then `fixed_steps` as 4 bytes
+    /// and `hitch` as 1 byte.
+    pub fn to_bytes(&self) -> [u8; DebugSnapshot::ENCODED_LEN] {
+        let nanos = |duration: Duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let mut bytes = [0; DebugSnapshot::ENCODED_LEN];
+        let words = [
+            self.frame_number.to_le_bytes(),
+            self.fps.to_le_bytes(),
+            nanos(self.delta_time).to_le_bytes(),
+            nanos(self.delta_real_time).to_le_bytes(),
+            self.time_scale.to_le_bytes(),
+            self.accumulator_fill.to_le_bytes(),
+        ];
+        for (chunk, word) in bytes.chunks_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(word);
+        }
+        bytes[48..52].copy_from_slice(&self.fixed_steps.to_le_bytes());
+        bytes[52] = u8::from(self.hitch);
+        bytes
+    }
+}
+
+/// Context of a frame that took longer than the slow-frame threshold of a clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlowFrame {
+    /// Number of the frame.
+    pub frame_number: u64,
+    /// Time elapsed since the previous frame ignoring the time speed multiplier.
+    pub delta_real_time: Duration,
+    /// The slow-frame threshold that was exceeded.
+    pub threshold: Duration,
+    /// Number of fixed steps due after advancing the frame.
+    pub steps_due: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn snapshot_bytes() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        let bytes = time.debug_snapshot().to_bytes();
+        assert_eq!(bytes[0..8], 1u64.to_le_bytes());
+        assert_eq!(bytes[16..24], 25_000_000u64.to_le_bytes());
+        assert_eq!(bytes[40..48], 2.5f64.to_le_bytes());
+        assert_eq!(bytes[52], 0);
+    }
+}
@@ -0,0 +1,39 @@
+//! Notable things a clock detected while advancing.
+
+use std::time::Duration;
+
+/// Something a clock detected during the current frame. See `Time::events`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockEvent {
+    /// The real delta of the frame was longer than the suspend threshold, most likely because
+    /// the machine slept or the app was suspended.
+    Resumed {
+        /// Real time that elapsed, before the suspend policy was applied.
+        gap: Duration,
+    },
+    /// The real delta of the frame was longer than the halt threshold, most likely because
+    /// execution stopped at a breakpoint or behind a blocking OS dialog. The frame was
+    /// advanced by the real delta of the previous frame instead.
+    Halted {
+        /// Real time that elapsed, before the previous delta was substituted.
+        gap: Duration,
+    },
+    /// The instant given to `Time::advance_frame_at` was not after the previous one, because
+    /// of a caller bug or a misbehaving OS clock. The frame was advanced by zero, raised to
+    /// the minimum delta if one is set.
+    NonMonotonic {
+        /// How far the instant went back. Zero if it was the same instant.
+        backwards: Duration,
+    },
+}
+
+/// What a clock does with the real delta of a frame after a suspension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuspendPolicy {
+    /// Counts the whole gap, for games where offline time matters (like idle games).
+    Keep,
+    /// Ignores the gap: the frame advances the clock by zero.
+    Discard,
+    /// Counts at most the given duration.
+    Clamp(Duration),
+}
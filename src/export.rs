@@ -0,0 +1,122 @@
+//! Streams per-frame timing records to a writer for offline analysis.
+
+use std::io::{self, Write};
+
+use crate::Time;
+
+/// Output format of a `FrameLogger`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Comma separated values, with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// Writes one record per frame: frame number, real delta, scaled delta, fixed steps and
+/// hitch flag. Deltas are written in nanoseconds.
+///
+/// The logger is only called when attached to a game loop, so it costs nothing otherwise.
+/// Wrap the writer in a `BufWriter` when writing to a file.
+#[derive(Debug)]
+pub struct FrameLogger<W: Write> {
+    writer: W,
+    format: LogFormat,
+    /// True once the CSV header was written.
+    header_written: bool,
+}
+
+impl<W: Write> FrameLogger<W> {
+    /// Creates a logger writing records in the given format.
+    pub fn new(writer: W, format: LogFormat) -> Self {
+        FrameLogger {
+            writer,
+            format,
+            header_written: false,
+        }
+    }
+
+    /// Gets the format of the records.
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Writes the record of the current frame. Call once per frame, after the fixed updates.
+    pub fn record(&mut self, time: &Time) -> io::Result<()> {
+        let frame_number = time.frame_number();
+        let delta_real = time.delta_real_time().as_nanos();
+        let delta = time.delta_time().as_nanos();
+        let fixed_steps = time.fixed_steps_this_frame();
+        let hitch = time.is_hitch();
+        match self.format {
+            LogFormat::Csv => {
+                if !self.header_written {
+                    writeln!(
+                        self.writer,
+                        "frame_number,delta_real_ns,delta_ns,fixed_steps,hitch"
+                    )?;
+                    self.header_written = true;
+                }
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{}",
+                    frame_number, delta_real, delta, fixed_steps, hitch
+                )
+            }
+            LogFormat::JsonLines => writeln!(
+                self.writer,
+                "{{\"frame_number\":{},\"delta_real_ns\":{},\"delta_ns\":{},\"fixed_steps\":{},\"hitch\":{}}}",
+                frame_number, delta_real, delta, fixed_steps, hitch
+            ),
+        }
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Gets back the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    fn log(format: LogFormat) -> String {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale(2.0);
+        let mut logger = FrameLogger::new(vec![], format);
+        for delta in &[15, 150] {
+            time.advance_frame(Duration::from_millis(*delta));
+            while time.step_fixed_update() {}
+            logger.record(&time).unwrap();
+        }
+        String::from_utf8(logger.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn csv_records() {
+        assert_eq!(
+            log(LogFormat::Csv),
+            "frame_number,delta_real_ns,delta_ns,fixed_steps,hitch\n\
+             1,15000000,30000000,1,false\n\
+             2,150000000,300000000,15,true\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_records() {
+        let lines = log(LogFormat::JsonLines);
+        assert_eq!(
+            lines.lines().next().unwrap(),
+            "{\"frame_number\":1,\"delta_real_ns\":15000000,\"delta_ns\":30000000,\"fixed_steps\":1,\"hitch\":false}"
+        );
+        assert_eq!(lines.lines().count(), 2);
+    }
+}
@@ -0,0 +1,394 @@
+//! C API, enabled with the `ffi` feature. The matching header is `include/game_clock.h`.
+//!
+//! Clocks are opaque `GameClockTime` pointers created by `game_clock_time_new` and destroyed
+//! by `game_clock_time_free`. Countdowns and schedulers work the same way. Every duration is
+//! passed as nanoseconds.
+//!
+//! The API only uses integers, doubles, booleans and opaque pointers, so any language with a
+//! C foreign function interface can call it.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::{Countdown, Handle, PackedTime, Scheduler, Time, TimerMode};
+
+/// Creates a clock with the given fixed time step, in nanoseconds, and a time scale of 1.
+/// Returns null if the fixed time step is zero, with which fixed update loops never end.
+#[no_mangle]
+pub extern "C" fn game_clock_time_new(fixed_step_nanos: u64) -> *mut Time {
+    if fixed_step_nanos == 0 {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(Time::new(fixed_step_nanos, 1.0)))
+}
+
+/// Destroys a clock. Does nothing if `time` is null.
+///
+/// # Safety
+/// `time` must be null or come from `game_clock_time_new`, and must not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_free(time: *mut Time) {
+    if !time.is_null() {
+        drop(Box::from_raw(time));
+    }
+}
+
+/// See `Time::advance_frame`.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_advance_frame(time: *mut Time, delta_nanos: u64) {
+    (*time).advance_frame(Duration::from_nanos(delta_nanos));
+}
+
+/// See `Time::step_fixed_update`.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_step_fixed_update(time: *mut Time) -> bool {
+    (*time).step_fixed_update()
+}
+
+/// See `Time::set_fixed_time`. Returns false, leaving the fixed time step unchanged, if it
+/// is zero.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_set_fixed_time(
+    time: *mut Time,
+    fixed_step_nanos: u64,
+) -> bool {
+    if fixed_step_nanos == 0 {
+        return false;
+    }
+    (*time).set_fixed_time(Duration::from_nanos(fixed_step_nanos));
+    true
+}
+
+/// See `Time::set_time_scale_f64`. Returns false, leaving the scale unchanged, if the
+/// multiplier is NaN, Infinity, or less than 0.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_set_time_scale(time: *mut Time, multiplier: f64) -> bool {
+    if multiplier >= 0.0 && multiplier != f64::INFINITY {
+        (*time).set_time_scale_f64(multiplier);
+        true
+    } else {
+        false
+    }
+}
+
+/// See `Time::time_scale_f64`.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_time_scale(time: *const Time) -> f64 {
+    (*time).time_scale_f64()
+}
+
+/// Gets the frequently read values of the clock. See `Time::pack`.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_pack(time: *const Time) -> PackedTime {
+    (*time).pack()
+}
+
+/// See `Time::fixed_step_count`.
+///
+/// # Safety
+/// `time` must be a valid clock.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_time_fixed_step_count(time: *const Time) -> u64 {
+    (*time).fixed_step_count()
+}
+
+/// Creates a countdown of the given duration, following real time if `real_time` is true
+/// and scaled game time otherwise.
+#[no_mangle]
+pub extern "C" fn game_clock_countdown_new(duration_nanos: u64, real_time: bool) -> *mut Countdown {
+    let mode = if real_time {
+        TimerMode::RealTime
+    } else {
+        TimerMode::GameTime
+    };
+    Box::into_raw(Box::new(Countdown::new(
+        Duration::from_nanos(duration_nanos),
+        mode,
+    )))
+}
+
+/// Destroys a countdown. Does nothing if `countdown` is null.
+///
+/// # Safety
+/// `countdown` must be null or come from `game_clock_countdown_new`, and must not be used
+/// afterward.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_free(countdown: *mut Countdown) {
+    if !countdown.is_null() {
+        drop(Box::from_raw(countdown));
+    }
+}
+
+/// See `Countdown::update`.
+///
+/// # Safety
+/// `countdown` and `time` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_update(countdown: *mut Countdown, time: *const Time) {
+    (*countdown).update(&*time);
+}
+
+/// See `Countdown::remaining`.
+///
+/// # Safety
+/// `countdown` must be a valid countdown.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_remaining(countdown: *const Countdown) -> u64 {
+    (*countdown).remaining().as_nanos() as u64
+}
+
+/// See `Countdown::add_time`.
+///
+/// # Safety
+/// `countdown` must be a valid countdown.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_add_time(countdown: *mut Countdown, nanos: u64) {
+    (*countdown).add_time(Duration::from_nanos(nanos));
+}
+
+/// See `Countdown::remove_time`.
+///
+/// # Safety
+/// `countdown` must be a valid countdown.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_remove_time(countdown: *mut Countdown, nanos: u64) {
+    (*countdown).remove_time(Duration::from_nanos(nanos));
+}
+
+/// See `Countdown::just_expired`.
+///
+/// # Safety
+/// `countdown` must be a valid countdown.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_just_expired(countdown: *const Countdown) -> bool {
+    (*countdown).just_expired()
+}
+
+/// See `Countdown::is_expired`.
+///
+/// # Safety
+/// `countdown` must be a valid countdown.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_countdown_is_expired(countdown: *const Countdown) -> bool {
+    (*countdown).is_expired()
+}
+
+/// Handle returned when a call could not be scheduled.
+pub const GAME_CLOCK_INVALID_HANDLE: u64 = u64::MAX;
+
+/// A `Scheduler` whose calls, instead of running closures, queue their handle to be read by
+/// `game_clock_scheduler_next_fired`.
+pub struct GameClockScheduler {
+    scheduler: Scheduler<VecDeque<u64>>,
+    fired: VecDeque<u64>,
+}
+
+impl GameClockScheduler {
+    /// Schedules a call pushing its own handle when it runs.
+    fn insert(&mut self, schedule: impl FnOnce(&mut Self, Rc<Cell<u64>>) -> Handle) -> u64 {
+        let id = Rc::new(Cell::new(GAME_CLOCK_INVALID_HANDLE));
+        let handle = schedule(self, Rc::clone(&id)).to_raw();
+        id.set(handle);
+        handle
+    }
+}
+
+/// Creates a scheduler following scaled game time, or real time if `real_time` is true.
+#[no_mangle]
+pub extern "C" fn game_clock_scheduler_new(real_time: bool) -> *mut GameClockScheduler {
+    let mut scheduler = Scheduler::new();
+    if real_time {
+        scheduler.set_mode(TimerMode::RealTime);
+    }
+    Box::into_raw(Box::new(GameClockScheduler {
+        scheduler,
+        fired: VecDeque::new(),
+    }))
+}
+
+/// Destroys a scheduler. Does nothing if `scheduler` is null.
+///
+/// # Safety
+/// `scheduler` must be null or come from `game_clock_scheduler_new`, and must not be used
+/// afterward.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_free(scheduler: *mut GameClockScheduler) {
+    if !scheduler.is_null() {
+        drop(Box::from_raw(scheduler));
+    }
+}
+
+/// Schedules a call once `delay_nanos` has passed, and returns its handle.
+/// See `Scheduler::schedule_after`.
+///
+/// # Safety
+/// `scheduler` must be a valid scheduler.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_schedule_after(
+    scheduler: *mut GameClockScheduler,
+    delay_nanos: u64,
+) -> u64 {
+    (*scheduler).insert(|this, id| {
+        this.scheduler
+            .schedule_after(Duration::from_nanos(delay_nanos), move |fired| {
+                fired.push_back(id.get())
+            })
+    })
+}
+
+/// Schedules a call every `interval_nanos`, and returns its handle.
+/// Returns `GAME_CLOCK_INVALID_HANDLE` if the interval is zero. See `Scheduler::every`.
+///
+/// # Safety
+/// `scheduler` must be a valid scheduler.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_every(
+    scheduler: *mut GameClockScheduler,
+    interval_nanos: u64,
+) -> u64 {
+    if interval_nanos == 0 {
+        return GAME_CLOCK_INVALID_HANDLE;
+    }
+    (*scheduler).insert(|this, id| {
+        this.scheduler
+            .every(Duration::from_nanos(interval_nanos), move |fired| {
+                fired.push_back(id.get())
+            })
+    })
+}
+
+/// See `Scheduler::cancel`.
+///
+/// # Safety
+/// `scheduler` must be a valid scheduler.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_cancel(
+    scheduler: *mut GameClockScheduler,
+    handle: u64,
+) -> bool {
+    (*scheduler).scheduler.cancel(Handle::from_raw(handle))
+}
+
+/// Follows the clock and runs the calls that are due, queuing their handles in due order.
+/// Returns how many ran. See `Scheduler::update` and `Scheduler::run_due`.
+///
+/// # Safety
+/// `scheduler` and `time` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_update(
+    scheduler: *mut GameClockScheduler,
+    time: *const Time,
+) -> u64 {
+    let this = &mut *scheduler;
+    this.scheduler.update(&*time);
+    this.scheduler.run_due(&mut this.fired) as u64
+}
+
+/// Takes the handle of the oldest call that ran and was not read yet, writing it to `handle`.
+/// Returns false if there is none.
+///
+/// # Safety
+/// `scheduler` must be a valid scheduler, and `handle` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_next_fired(
+    scheduler: *mut GameClockScheduler,
+    handle: *mut u64,
+) -> bool {
+    match (*scheduler).fired.pop_front() {
+        Some(fired) => {
+            *handle = fired;
+            true
+        }
+        None => false,
+    }
+}
+
+/// See `Scheduler::len`.
+///
+/// # Safety
+/// `scheduler` must be a valid scheduler.
+#[no_mangle]
+pub unsafe extern "C" fn game_clock_scheduler_len(scheduler: *const GameClockScheduler) -> u64 {
+    (*scheduler).scheduler.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::*;
+
+    #[test]
+    fn c_api_round_trip() {
+        unsafe {
+            assert!(game_clock_time_new(0).is_null());
+            let time = game_clock_time_new(10_000_000);
+            assert!(!game_clock_time_set_fixed_time(time, 0));
+            assert!(game_clock_time_set_time_scale(time, 2.0));
+            assert!(!game_clock_time_set_time_scale(time, f64::NAN));
+            game_clock_time_advance_frame(time, 25_000_000);
+            let mut steps = 0;
+            while game_clock_time_step_fixed_update(time) {
+                steps += 1;
+            }
+            assert_eq!(steps, 2);
+            assert_eq!(game_clock_time_fixed_step_count(time), 2);
+            let packed = game_clock_time_pack(time);
+            assert_eq!(packed.absolute_nanos, 50_000_000);
+            assert_eq!(game_clock_time_time_scale(time), 2.0);
+            game_clock_time_free(time);
+            game_clock_time_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn c_timers() {
+        unsafe {
+            let time = game_clock_time_new(10_000_000);
+            let countdown = game_clock_countdown_new(30_000_000, false);
+            let scheduler = game_clock_scheduler_new(false);
+            let once = game_clock_scheduler_schedule_after(scheduler, 25_000_000);
+            let repeat = game_clock_scheduler_every(scheduler, 20_000_000);
+            assert_eq!(
+                game_clock_scheduler_every(scheduler, 0),
+                GAME_CLOCK_INVALID_HANDLE
+            );
+
+            game_clock_time_advance_frame(time, 45_000_000);
+            game_clock_countdown_update(countdown, time);
+            assert!(game_clock_countdown_just_expired(countdown));
+            assert_eq!(game_clock_countdown_remaining(countdown), 0);
+            assert_eq!(game_clock_scheduler_update(scheduler, time), 3);
+            let mut fired = vec![];
+            let mut handle = 0;
+            while game_clock_scheduler_next_fired(scheduler, &mut handle) {
+                fired.push(handle);
+            }
+            assert_eq!(fired, vec![repeat, once, repeat]);
+            assert!(game_clock_scheduler_cancel(scheduler, repeat));
+            assert_eq!(game_clock_scheduler_len(scheduler), 0);
+
+            game_clock_scheduler_free(scheduler);
+            game_clock_countdown_free(countdown);
+            game_clock_time_free(time);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Detects when the player stopped interacting with the game.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// A change between the active and idle states of an `IdleTracker`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// The player has been inactive for longer than the given threshold.
+    Idle {
+        /// The threshold that was crossed.
+        threshold: Duration,
+    },
+    /// The player came back after being idle.
+    Active {
+        /// Real time the player was inactive for.
+        idle_for: Duration,
+    },
+}
+
+/// Tracks the real time elapsed since the last input of the player, for attract modes,
+/// auto-pause or "away" online presence.
+///
+/// Call `note_activity` for every input event and `update` once per frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdleTracker {
+    /// Real absolute time of the clock at the last update, in nanoseconds.
+    now: u64,
+    /// Real absolute time of the last activity, in nanoseconds.
+    last_activity: u64,
+    /// Idle thresholds, shortest first.
+    thresholds: Vec<Duration>,
+    /// Number of thresholds crossed since the last activity.
+    crossed: usize,
+    /// Transitions waiting to be returned by `update`.
+    pending: Vec<IdleEvent>,
+}
+
+impl IdleTracker {
+    /// Creates a tracker considering the player active at the current time of the clock.
+    pub fn new(time: &Time) -> Self {
+        let now = crate::to_nanos(time.absolute_real_time());
+        IdleTracker {
+            now,
+            last_activity: now,
+            thresholds: vec![],
+            crossed: 0,
+            pending: vec![],
+        }
+    }
+
+    /// Adds an idle threshold. `update` reports an `IdleEvent::Idle` when the player has been
+    /// inactive for longer than it.
+    pub fn add_threshold(&mut self, threshold: Duration) {
+        let index = self
+            .thresholds
+            .iter()
+            .position(|t| *t > threshold)
+            .unwrap_or(self.thresholds.len());
+        self.thresholds.insert(index, threshold);
+        if index < self.crossed {
+            self.crossed += 1;
+        }
+    }
+
+    /// Gets the idle thresholds, shortest first.
+    pub fn thresholds(&self) -> &[Duration] {
+        &self.thresholds
+    }
+
+    /// Records an input of the player.
+    pub fn note_activity(&mut self) {
+        if self.crossed > 0 {
+            self.pending.push(IdleEvent::Active {
+                idle_for: self.idle_for(),
+            });
+            self.crossed = 0;
+        }
+        self.last_activity = self.now;
+    }
+
+    /// Gets the real time elapsed since the last input, as of the last update.
+    pub fn idle_for(&self) -> Duration {
+        Duration::from_nanos(self.now - self.last_activity)
+    }
+
+    /// Checks if the player is inactive for longer than at least one threshold.
+    pub fn is_idle(&self) -> bool {
+        self.crossed > 0
+    }
+
+    /// Moves the tracker to the current time of the clock and returns the transitions that
+    /// happened since the last update, in order.
+    pub fn update(&mut self, time: &Time) -> Vec<IdleEvent> {
+        self.now = self.now.max(crate::to_nanos(time.absolute_real_time()));
+        let mut events = std::mem::take(&mut self.pending);
+        let idle_for = self.idle_for();
+        while let Some(threshold) = self.thresholds.get(self.crossed) {
+            if idle_for <= *threshold {
+                break;
+            }
+            events.push(IdleEvent::Idle {
+                threshold: *threshold,
+            });
+            self.crossed += 1;
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn idle_and_active_transitions() {
+        let mut time = Time::default();
+        let mut tracker = IdleTracker::new(&time);
+        tracker.add_threshold(Duration::from_secs(300));
+        tracker.add_threshold(Duration::from_secs(60));
+
+        time.advance_frame(Duration::from_secs(30));
+        assert!(tracker.update(&time).is_empty());
+        tracker.note_activity();
+
+        time.advance_frame(Duration::from_secs(400));
+        assert_eq!(
+            tracker.update(&time),
+            vec![
+                IdleEvent::Idle {
+                    threshold: Duration::from_secs(60)
+                },
+                IdleEvent::Idle {
+                    threshold: Duration::from_secs(300)
+                },
+            ]
+        );
+        assert!(tracker.is_idle());
+
+        tracker.note_activity();
+        time.advance_frame(Duration::from_secs(1));
+        assert_eq!(
+            tracker.update(&time),
+            vec![IdleEvent::Active {
+                idle_for: Duration::from_secs(400)
+            }]
+        );
+        assert_eq!(tracker.idle_for(), Duration::from_secs(1));
+        assert!(!tracker.is_idle());
+    }
+}
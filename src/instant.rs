@@ -0,0 +1,154 @@
+//! Typed points and spans on the scaled and real timelines, so they can't be mixed up.
+
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
+
+macro_rules! timeline {
+    ($timeline:literal, $instant:ident, $duration:ident) => {
+        #[doc = concat!("A point on the ", $timeline, " timeline, measured from game start.")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $instant(Duration);
+
+        impl $instant {
+            /// The start of the game.
+            pub const START: $instant = $instant(Duration::from_secs(0));
+
+            /// Creates the point at the given time since game start.
+            pub const fn from_start(since_start: Duration) -> Self {
+                $instant(since_start)
+            }
+
+            /// Gets the time since game start.
+            pub const fn since_start(self) -> Duration {
+                self.0
+            }
+
+            /// Gets the time elapsed from `earlier` to this point, or zero if `earlier` is later.
+            pub fn saturating_duration_since(self, earlier: $instant) -> $duration {
+                $duration(self.0.checked_sub(earlier.0).unwrap_or_default())
+            }
+        }
+
+        #[doc = concat!("A span of ", $timeline, " time.")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $duration(Duration);
+
+        impl $duration {
+            /// A span of zero.
+            pub const ZERO: $duration = $duration(Duration::from_secs(0));
+
+            /// Creates a span of the given length.
+            pub const fn new(duration: Duration) -> Self {
+                $duration(duration)
+            }
+
+            /// Gets the length of the span.
+            pub const fn duration(self) -> Duration {
+                self.0
+            }
+        }
+
+        impl From<$duration> for Duration {
+            fn from(duration: $duration) -> Duration {
+                duration.0
+            }
+        }
+
+        impl Add<$duration> for $instant {
+            type Output = $instant;
+
+            fn add(self, rhs: $duration) -> $instant {
+                $instant(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign<$duration> for $instant {
+            fn add_assign(&mut self, rhs: $duration) {
+                self.0 += rhs.0;
+            }
+        }
+
+        /// ## Panics
+        /// This will panic if the result is before game start.
+        impl Sub<$duration> for $instant {
+            type Output = $instant;
+
+            fn sub(self, rhs: $duration) -> $instant {
+                $instant(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign<$duration> for $instant {
+            fn sub_assign(&mut self, rhs: $duration) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        /// Saturates to zero if `rhs` is later, like `std::time::Instant`.
+        impl Sub<$instant> for $instant {
+            type Output = $duration;
+
+            fn sub(self, rhs: $instant) -> $duration {
+                self.saturating_duration_since(rhs)
+            }
+        }
+
+        impl Add for $duration {
+            type Output = $duration;
+
+            fn add(self, rhs: $duration) -> $duration {
+                $duration(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign for $duration {
+            fn add_assign(&mut self, rhs: $duration) {
+                self.0 += rhs.0;
+            }
+        }
+
+        /// ## Panics
+        /// This will panic if the result is negative.
+        impl Sub for $duration {
+            type Output = $duration;
+
+            fn sub(self, rhs: $duration) -> $duration {
+                $duration(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign for $duration {
+            fn sub_assign(&mut self, rhs: $duration) {
+                self.0 -= rhs.0;
+            }
+        }
+    };
+}
+
+timeline!("scaled game", GameInstant, GameDuration);
+timeline!("real", RealInstant, RealDuration);
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn typed_timelines() {
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        let start = time.game_now();
+        time.advance_frame(Duration::from_millis(100));
+
+        assert_eq!(time.game_now() - start, time.game_delta());
+        assert_eq!(
+            time.game_delta(),
+            GameDuration::new(Duration::from_millis(200))
+        );
+        assert_eq!(
+            time.real_now(),
+            RealInstant::START + RealDuration::new(Duration::from_millis(100))
+        );
+        assert_eq!(start - time.game_now(), GameDuration::ZERO);
+    }
+}
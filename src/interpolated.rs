@@ -0,0 +1,102 @@
+//! Rendering fixed-step state between its two last values.
+
+use crate::Time;
+
+/// Values that can be linearly interpolated.
+pub trait Lerp {
+    /// Gets the value a fraction `t` of the way from `self` to `other`.
+    fn lerp(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t as f32
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl<T: Lerp, const N: usize> Lerp for [T; N] {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        std::array::from_fn(|i| self[i].lerp(&other[i], t))
+    }
+}
+
+/// The previous and current values of a piece of fixed-step state, like a position, which
+/// renders in between using `Time::fixed_step_alpha`.
+///
+/// Call `step` at the start of every fixed step and change the value it returns, then read
+/// `value` when rendering.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T: Clone> Interpolated<T> {
+    /// Creates a value that has not moved yet.
+    pub fn new(value: T) -> Self {
+        Interpolated {
+            previous: value.clone(),
+            current: value,
+        }
+    }
+
+    /// Starts a fixed step: the current value becomes the previous one, and is returned to
+    /// be changed by the step.
+    pub fn step(&mut self) -> &mut T {
+        self.previous = self.current.clone();
+        &mut self.current
+    }
+
+    /// Sets both values, so that the change is not interpolated, for example on teleports.
+    pub fn teleport(&mut self, value: T) {
+        self.previous = value.clone();
+        self.current = value;
+    }
+}
+
+impl<T> Interpolated<T> {
+    /// Gets the value before the last fixed step.
+    pub fn previous(&self) -> &T {
+        &self.previous
+    }
+
+    /// Gets the value after the last fixed step.
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+}
+
+impl<T: Lerp> Interpolated<T> {
+    /// Gets the value to render, between the two last fixed steps.
+    pub fn value(&self, time: &Time) -> T {
+        self.previous.lerp(&self.current, time.fixed_step_alpha())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn renders_between_fixed_steps() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        let mut position = Interpolated::new([0.0f32, 0.0]);
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {
+            position.step()[0] += 1.0;
+        }
+        assert_eq!(position.previous(), &[1.0, 0.0]);
+        assert_eq!(position.value(&time), [1.5, 0.0]);
+
+        position.teleport([10.0, 10.0]);
+        assert_eq!(position.value(&time), [10.0, 10.0]);
+    }
+}
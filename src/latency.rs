@@ -0,0 +1,121 @@
+//! Time between simulating a frame and showing it on screen.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames simulated but not presented yet, past which the oldest are forgotten.
+const MAX_PENDING: usize = 16;
+
+/// Rolling estimate of the simulation to present latency, for aim assist, input prediction
+/// and latency displays.
+///
+/// Tag each frame with `simulated` when its simulation state is sampled and `presented` when
+/// it reaches the screen, for example after the swap buffers call returns or from a present
+/// timing extension.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencyEstimator {
+    /// Frames simulated and not presented yet, oldest first.
+    pending: VecDeque<(u64, Instant)>,
+    /// Latencies of the last presented frames, oldest first.
+    samples: VecDeque<Duration>,
+    /// Maximum number of samples kept.
+    window: usize,
+    /// Simulation time of the frame on screen.
+    on_screen: Option<Instant>,
+}
+
+impl LatencyEstimator {
+    /// Creates an estimator averaging the latency of the last `window` presented frames.
+    ///
+    /// ## Panics
+    /// This will panic if window is 0.
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0);
+        LatencyEstimator {
+            pending: VecDeque::new(),
+            samples: VecDeque::with_capacity(window),
+            window,
+            on_screen: None,
+        }
+    }
+
+    /// Records when the simulation state of `frame` was sampled.
+    pub fn simulated(&mut self, frame: u64, at: Instant) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((frame, at));
+    }
+
+    /// Records when `frame` was presented, and returns its latency.
+    /// Returns `None` if the simulation time of the frame was not recorded.
+    pub fn presented(&mut self, frame: u64, at: Instant) -> Option<Duration> {
+        let index = self.pending.iter().position(|(id, _)| *id == frame)?;
+        // Frames simulated before this one will never be presented.
+        let (_, simulated) = self.pending.drain(..=index).next_back()?;
+        let latency = at.saturating_duration_since(simulated);
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+        self.on_screen = Some(simulated);
+        Some(latency)
+    }
+
+    /// Gets the latency of the last presented frame.
+    pub fn latest(&self) -> Option<Duration> {
+        self.samples.back().copied()
+    }
+
+    /// Gets the average latency of the last presented frames.
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+    }
+
+    /// Gets the highest latency of the last presented frames.
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+
+    /// Gets how old the simulation state of the frame on screen is at `now`.
+    pub fn frame_age(&self, now: Instant) -> Option<Duration> {
+        self.on_screen
+            .map(|simulated| now.saturating_duration_since(simulated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rolling_latency() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut latency = LatencyEstimator::new(2);
+        latency.simulated(1, ms(0));
+        latency.simulated(2, ms(16));
+        latency.simulated(3, ms(32));
+        assert_eq!(
+            latency.presented(1, ms(30)),
+            Some(Duration::from_millis(30))
+        );
+        // Frame 2 was dropped by the swap chain.
+        assert_eq!(
+            latency.presented(3, ms(72)),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(latency.presented(2, ms(80)), None);
+        assert_eq!(latency.average(), Some(Duration::from_millis(35)));
+
+        latency.simulated(4, ms(48));
+        latency.presented(4, ms(98));
+        assert_eq!(latency.average(), Some(Duration::from_millis(45)));
+        assert_eq!(latency.max(), Some(Duration::from_millis(50)));
+        assert_eq!(latency.frame_age(ms(100)), Some(Duration::from_millis(52)));
+    }
+}
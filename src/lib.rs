@@ -5,119 +5,1981 @@
 //! This is a rework of the original `Time` struct. It has been heavily simplified
 //! and documentation has been added.
 
-use std::time::Duration;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-/// Frame timing values.
+mod alarm;
+mod blinker;
+mod calendar;
+mod clock_set;
+mod command;
+mod countdown;
+mod curve;
+mod damping;
+mod debug;
+mod easing;
+mod event;
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod idle;
+mod instant;
+mod interpolated;
+mod latency;
+mod limiter;
+mod local;
+mod mutation;
+mod net;
+mod oscillator;
+mod phase;
+mod playtime;
+mod region;
+mod rng;
+mod scheduler;
+mod shared;
+mod source;
+mod state;
+mod stats;
+mod stepping;
+mod substep;
+#[cfg(feature = "test-strategies")]
+pub mod testing;
+mod ticker;
+mod timeline;
+mod timestamp;
+mod trace;
+mod track;
+mod view;
+mod watchdog;
+mod wheel;
+
+pub use crate::alarm::{AlarmClock, AlarmId};
+pub use crate::blinker::{BlinkEvent, Blinker};
+pub use crate::calendar::{
+    Calendar, CalendarEvents, CalendarSchedule, GameDateTime, SeasonTime, Seasons,
+};
+pub use crate::clock_set::{ClockDrift, ClockSet, DriftReport};
+pub use crate::command::{CommandQueue, LateCommand};
+pub use crate::countdown::Countdown;
+pub use crate::curve::{Curve, CurveInterpolation, CurveKey, LoopMode};
+pub use crate::damping::{damp, damp_factor, smooth_lerp};
+pub use crate::debug::{DebugSnapshot, SlowFrame};
+pub use crate::easing::Easing;
+pub use crate::event::{ClockEvent, SuspendPolicy};
+pub use crate::export::{FrameLogger, LogFormat};
+pub use crate::idle::{IdleEvent, IdleTracker};
+pub use crate::instant::{GameDuration, GameInstant, RealDuration, RealInstant};
+pub use crate::interpolated::{Interpolated, Lerp};
+pub use crate::latency::LatencyEstimator;
+pub use crate::limiter::{
+    FrameLimiter, InterruptibleLimiter, LimiterStrategy, ServerWait, WakeHandle,
+};
+pub use crate::local::LocalClock;
+pub use crate::mutation::{ClockMutation, LoggedMutation};
+pub use crate::net::{ClockSlew, InterpolationWindow};
+pub use crate::oscillator::{Oscillator, Waveform};
+pub use crate::phase::FixedUpdateGuard;
+pub use crate::playtime::{Playtime, PlaytimeSource};
+pub use crate::region::{RegionBlend, ScaleRegion};
+pub use crate::scheduler::{
+    Handle, PendingCall, Scheduler, SchedulerBackend, SchedulerState, TimerMode,
+};
+pub use crate::shared::{PackedTime, SharedTime};
+pub use crate::source::{ClockSource, ManualClock, SystemClock};
+pub use crate::state::{TimeState, TimeStateLatest, TimeStateV1, TimeStateV2};
+pub use crate::stats::{
+    FixedStepHistogram, FramePhases, FrameRecord, FrameStats, SessionStats, SessionSummary,
+};
+pub use crate::stepping::{
+    ClampedStepping, SemiFixedStepping, StepFrame, StepPlan, SteppingPolicy,
+};
+pub use crate::substep::SubStepper;
+pub use crate::ticker::RandomTicker;
+pub use crate::timeline::{Timeline, TimelineEvent};
+pub use crate::timestamp::GameTimestamp;
+pub use crate::trace::{TimingTrace, TraceFrame, TraceMismatch, TraceParseError, TracePlayer};
+pub use crate::track::{ScaleKey, ScaleTrack};
+pub use crate::view::{GameClock, TimeView};
+pub use crate::watchdog::{LoopStall, Watchdog};
+
+/// How the fixed timestep accumulator is drained after a long frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CatchUp {
+    /// Runs every fixed step that is due, all in the current frame.
+    #[default]
+    Immediate,
+    /// Runs at most `max_extra_steps` fixed steps per frame on top of the ones covered by a
+    /// regular frame. A frame much longer than the previous one is treated as a hitch, and the
+    /// backlog it creates is bled off over the following frames.
+    Smooth {
+        /// Extra fixed steps allowed per frame while catching up.
+        max_extra_steps: u32,
+    },
+}
+
+/// What happens to the fixed timestep accumulator when the fixed time step changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RateChangePolicy {
+    /// Keeps the time waiting in the accumulator, which then covers a different number
+    /// of steps.
+    #[default]
+    Keep,
+    /// Scales the accumulator with the step, keeping the number of steps due and the
+    /// interpolation alpha, so no step is dropped or duplicated by the switch.
+    Proportional,
+    /// Empties the accumulator: the fixed steps at the new rate start from scratch.
+    Flush,
+}
+
+/// How fractions of a nanosecond are handled when the time scale is applied to a real delta.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DeltaRounding {
+    /// Rounds each scaled delta down. Scaled absolute time drifts behind, by up to a
+    /// nanosecond per frame.
+    Truncate,
+    /// Rounds each scaled delta to the nearest nanosecond. Errors mostly cancel out, but can
+    /// still build up when every frame rounds the same way.
+    Nearest,
+    /// Rounds each scaled delta down and carries the dropped fraction over to the next frame,
+    /// so scaled absolute time is exactly real time times the scale.
+    #[default]
+    Carry,
+}
+
+/// Accumulator of a secondary fixed time step, added with `Time::set_fixed_rate`.
+#[derive(Clone, Debug, PartialEq)]
+struct FixedRate {
+    /// Fixed time step, in nanoseconds.
+    step: u64,
+    /// Time waiting for the next step, in nanoseconds.
+    accumulator: u64,
+    /// Sum of the fixed time steps run, in nanoseconds.
+    last_step_time: u64,
+}
+
+/// Result of `Time::change_fixed_time`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RateChange {
+    /// First fixed tick, numbered like `Time::fixed_step_count`, run at the new rate.
+    pub first_tick: u64,
+    /// Fixed steps that were due at the old rate.
+    pub steps_due_before: u64,
+    /// Fixed steps due at the new rate.
+    pub steps_due_after: u64,
+    /// Time removed from the accumulator.
+    pub discarded: Duration,
+}
+
+/// Exact ratio converting real nanoseconds into scaled nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ScaleRatio {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl ScaleRatio {
+    /// Converts a float multiplier into a ratio over 2^32, rounding it to the nearest multiple
+    /// of 2^-32. Multipliers of 2^32 and above saturate to just under 2^32.
+    const fn from_f64(scale: f64) -> Self {
+        ScaleRatio {
+            numerator: round_to_u64(scale * (1u64 << 32) as f64),
+            denominator: 1 << 32,
+        }
+    }
+
+    /// Creates a ratio reduced to its lowest terms.
+    fn reduced(numerator: u128, denominator: u128) -> Option<Self> {
+        let divisor = gcd(numerator, denominator).max(1);
+        let numerator = u64::try_from(numerator / divisor).ok()?;
+        let denominator = u64::try_from(denominator / divisor).ok()?;
+        Some(ScaleRatio {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Multiplies two ratios, if the result still fits.
+    fn checked_mul(self, other: ScaleRatio) -> Option<Self> {
+        ScaleRatio::reduced(
+            u128::from(self.numerator) * u128::from(other.numerator),
+            u128::from(self.denominator) * u128::from(other.denominator),
+        )
+    }
+
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Computes `nanos * numerator + extra` divided by the denominator, as a saturated
+    /// quotient and a remainder. This runs every frame, so u64 arithmetic is used whenever it
+    /// cannot overflow, u128 divisions being several times slower.
+    fn divide(self, nanos: u64, extra: u64) -> (u64, u64) {
+        if self.numerator == self.denominator && extra < self.denominator {
+            return (nanos, extra);
+        }
+        match nanos
+            .checked_mul(self.numerator)
+            .and_then(|product| product.checked_add(extra))
+        {
+            Some(total) => (total / self.denominator, total % self.denominator),
+            None => {
+                let total = u128::from(nanos) * u128::from(self.numerator) + u128::from(extra);
+                let denominator = u128::from(self.denominator);
+                let quotient = (total / denominator).min(u128::from(u64::MAX)) as u64;
+                (quotient, (total % denominator) as u64)
+            }
+        }
+    }
+
+    /// Scales the given nanoseconds, rounding down.
+    fn apply(self, nanos: u64) -> u64 {
+        self.divide(nanos, 0).0
+    }
+
+    /// Scales the given nanoseconds, rounding down, and carries the fraction of a nanosecond
+    /// that was dropped (in units of `1 / denominator` nanoseconds) over to the next call.
+    fn apply_carrying(self, nanos: u64, remainder: &mut u64) -> u64 {
+        let (scaled, dropped) = self.divide(nanos, *remainder);
+        *remainder = dropped;
+        scaled
+    }
+
+    /// Scales the given nanoseconds, rounding to the nearest nanosecond.
+    fn apply_nearest(self, nanos: u64) -> u64 {
+        self.divide(nanos, self.denominator / 2).0
+    }
+
+    /// Reverses `apply`, rounding down. Saturates when the ratio is zero.
+    fn invert(self, nanos: u64) -> u64 {
+        if self.numerator == 0 {
+            return u64::MAX;
+        }
+        let real = u128::from(nanos) * u128::from(self.denominator) / u128::from(self.numerator);
+        real.min(u128::from(u64::MAX)) as u64
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Speed presets of a new clock: the usual speed control bar of strategy and tycoon games.
+pub const DEFAULT_SPEED_PRESETS: &[(&str, f32)] =
+    &[("0.5x", 0.5), ("1x", 1.0), ("2x", 2.0), ("4x", 4.0)];
+
+/// A transition of the base time scale in progress.
 #[derive(Clone, Copy, Debug, PartialEq)]
+struct ScaleRamp {
+    from: f64,
+    to: f64,
+    /// Real absolute time at which the ramp started, in nanoseconds.
+    start: u64,
+    /// Real time taken by the ramp, in nanoseconds.
+    duration: u64,
+    easing: Easing,
+}
+
+/// Closure called by a clock to report something, compared by address.
+/// Clones of a clock share the same closure.
+struct Hook<T>(Arc<Mutex<HookFn<T>>>);
+
+type HookFn<T> = dyn FnMut(&T) + Send;
+
+impl<T> Hook<T> {
+    fn call(&self, value: &T) {
+        let mut hook = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        (*hook)(value)
+    }
+}
+
+impl<T> Clone for Hook<T> {
+    fn clone(&self) -> Self {
+        Hook(Arc::clone(&self.0))
+    }
+}
+
+impl<T> std::fmt::Debug for Hook<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+impl<T> PartialEq for Hook<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Stepping policy of a clock, compared by address.
+/// Clones of a clock share the same policy.
+#[derive(Clone)]
+struct Policy(Arc<Mutex<dyn SteppingPolicy>>);
+
+impl Policy {
+    #[inline(never)]
+    fn plan(&self, frame: &StepFrame) -> StepPlan {
+        let mut policy = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        policy.plan(frame)
+    }
+}
+
+impl std::fmt::Debug for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0.try_lock() {
+            Ok(policy) => policy.fmt(f),
+            Err(_) => f.write_str("Policy"),
+        }
+    }
+}
+
+impl PartialEq for Policy {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Rounds a non-negative float to the nearest integer, halves away from zero.
+/// `f64::round` is not usable in a `const fn` on the supported compilers.
+const fn round_to_u64(value: f64) -> u64 {
+    let truncated = value as u64;
+    if value - truncated as f64 >= 0.5 {
+        truncated.saturating_add(1)
+    } else {
+        truncated
+    }
+}
+
+/// Hashes integers with 64 bit FNV-1a, which gives the same result on every platform and
+/// compiler version.
+fn fnv1a(values: &[u64]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for value in values {
+        for byte in &value.to_le_bytes() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Converts a `Duration` to nanoseconds, saturating after about 584 years.
+fn to_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u128::from(u64::MAX)) as u64
+}
+
+/// Panics with `message` if `valid` is false and the `strict` feature is enabled.
+#[track_caller]
+fn strict_assert(valid: bool, message: &str) {
+    if cfg!(feature = "strict") {
+        assert!(valid, "game_clock strict mode: {}", message);
+    }
+}
+
+/// Frame timing values.
+///
+/// Time is accumulated internally as integer nanoseconds, so that `absolute_time` is always
+/// exactly the sum of the scaled time of every frame, even over very long sessions. This is the
+/// sum of every `delta_time`, except for frames raised to the minimum delta, which report a
+/// longer delta than the time that passed (see `set_min_delta`).
+#[derive(Clone, Debug, PartialEq)]
 pub struct Time {
-    /// Time elapsed since the last frame.
-    delta_time: Duration,
-    /// Time elapsed since the last frame ignoring the time speed multiplier.
-    delta_real_time: Duration,
-    /// Rate at which `State::fixed_update` is called.
-    fixed_time: Duration,
+    /// Time elapsed since the last frame, in nanoseconds.
+    delta_time: u64,
+    /// Time elapsed since the last frame ignoring the time speed multiplier, in nanoseconds.
+    delta_real_time: u64,
+    /// Value of `delta_real_time` during the previous frame.
+    previous_delta_real_time: u64,
+    /// Real time added to `absolute_real_time` by the last frame, before the minimum delta.
+    frame_real_time: u64,
+    /// Scaled time added to `absolute_time` by the last frame, before the minimum delta.
+    frame_time: u64,
+    /// Rate at which `State::fixed_update` is called, in nanoseconds.
+    fixed_time: u64,
     /// The total number of frames that have been played in this session.
     frame_number: u64,
-    ///Time elapsed since game start, ignoring the speed multipler.
-    absolute_real_time: Duration,
-    ///Time elapsed since game start, taking the speed multiplier into account.
-    absolute_time: Duration,
+    ///Time elapsed since game start, ignoring the speed multipler, in nanoseconds.
+    absolute_real_time: u64,
+    ///Time elapsed since game start, taking the speed multiplier into account, in nanoseconds.
+    absolute_time: u64,
+    ///Time multiplier set by the user, before scale layers are applied.
+    base_time_scale: f64,
+    /// `base_time_scale` as an exact ratio, when it was set with `set_time_scale_ratio`.
+    base_scale_ratio: Option<ScaleRatio>,
+    /// Named multipliers applied on top of `base_time_scale`.
+    scale_layers: BTreeMap<String, f32>,
+    /// Transition of `base_time_scale` in progress.
+    scale_ramp: Option<ScaleRamp>,
+    /// Named base time scales selectable with `set_speed_preset`.
+    speed_presets: &'static [(&'static str, f32)],
+    /// Real time taken to switch between speed presets, in nanoseconds.
+    speed_ramp_duration: u64,
+    /// Track being played, and the real absolute time it started at, in nanoseconds.
+    scale_track: Option<(ScaleTrack, u64)>,
+    /// Multiplier currently given by `scale_track`.
+    track_scale: f32,
     ///Time multiplier. Affects returned delta_time and absolute_time.
-    time_scale: f32,
-    /// Fixed timestep accumulator.
-    fixed_time_accumulator: Duration,
+    time_scale: f64,
+    /// `time_scale` as the exact ratio used to compute scaled times.
+    scale_ratio: ScaleRatio,
+    /// Fraction of a scaled nanosecond left over by the previous frame,
+    /// in units of `1 / scale_ratio.denominator` nanoseconds.
+    scale_remainder: u64,
+    /// How scaled deltas are rounded.
+    rounding: DeltaRounding,
+    /// Fixed timestep accumulator, in nanoseconds.
+    fixed_time_accumulator: u64,
+    /// Named secondary fixed time steps, like a 10 Hz AI tick.
+    fixed_rates: BTreeMap<String, FixedRate>,
+    /// Smallest frame delta that will be reported, in nanoseconds. Zero disables the clamp.
+    min_delta: u64,
+    /// How the accumulator is drained after a long frame.
+    catch_up: CatchUp,
+    /// Replaces `catch_up` when set.
+    stepping_policy: Option<Policy>,
+    /// Fixed steps run since the last call to `advance_frame`.
+    fixed_steps_this_frame: u32,
+    /// Fixed steps run since the clock was created.
+    fixed_step_count: u64,
+    /// Sum of the fixed time steps run since the clock was created, in nanoseconds.
+    last_fixed_step_time: u64,
+    /// Fixed steps allowed during the current frame by the catch-up policy.
+    fixed_step_budget: u32,
+    /// True if the current frame ends with a shorter step consuming the rest of the
+    /// accumulator, planned by a semi-fixed stepping policy.
+    partial_step: bool,
+    /// Length of the last fixed step started, in nanoseconds. Shorter than `fixed_time` for
+    /// a partial step.
+    fixed_step_length: u64,
+    /// True if the last call to `step_fixed_update` started a fixed step.
+    in_fixed_step: bool,
+    /// True while a `FixedUpdateGuard` is alive.
+    fixed_phase: bool,
+    /// True if every due fixed step must run before the next frame. Checked in strict mode.
+    require_drained_fixed_steps: bool,
+    /// Accumulator fill above which rendering should be skipped.
+    render_skip_threshold: Option<Duration>,
+    /// Real frame delta above which a frame counts as a hitch, in nanoseconds.
+    hitch_threshold: u64,
+    /// Real frame delta above which `slow_frame_hook` is called.
+    slow_frame_threshold: Option<Duration>,
+    /// Called by `advance_frame` for frames slower than `slow_frame_threshold`.
+    slow_frame_hook: Option<Hook<SlowFrame>>,
+    /// Real delta above which the app is considered to have been suspended, and what to do then.
+    suspend_detection: Option<(Duration, SuspendPolicy)>,
+    /// Real delta above which execution is considered to have been halted, in nanoseconds.
+    halt_threshold: Option<u64>,
+    /// Frame rate of the capture mode, and frames advanced since it was enabled.
+    capture: Option<(u32, u64)>,
+    /// True if any of capture mode, suspend or halt detection, secondary fixed rates, frame
+    /// history, scale ramps and tracks or the slow frame hook is in use. Checked once per frame
+    /// to skip their work otherwise.
+    frame_extras: bool,
+    /// Maximum number of entries kept in `mutation_log`.
+    mutation_log_len: usize,
+    /// Recent changes made to the clock, oldest first.
+    mutation_log: VecDeque<LoggedMutation>,
+    /// Events detected during the current frame.
+    events: Vec<ClockEvent>,
+    /// Instant given to the last call to `advance_frame_at`: when the current frame began.
+    frame_start: Option<Instant>,
+    /// False while the app window lost focus.
+    focused: bool,
+    /// True if scaled time and fixed steps stop while the app is unfocused.
+    freeze_when_unfocused: bool,
+    /// Number of excluded sections currently open.
+    excluded_sections: u32,
+    /// True if an excluded section was open at some point since the last frame.
+    section_opened: bool,
+    /// True if the current frame was excluded from scaled time.
+    frame_excluded: bool,
+    /// Real time of the excluded frames, in nanoseconds.
+    excluded_time: u64,
+    /// Maximum number of frames kept in `frame_history`.
+    frame_history_len: usize,
+    /// `(absolute_time, absolute_real_time)` at the end of the last frames, oldest first,
+    /// in nanoseconds. The last entry is the current frame.
+    frame_history: VecDeque<(u64, u64)>,
 }
 
 impl Time {
-    /// Gets the time difference between frames.
+    /// Creates a clock with the given fixed time step, in nanoseconds, and time multiplier.
+    ///
+    /// This is a `const fn`, so clocks and default configurations can be stored in constants
+    /// and statics.
+    ///
+    /// ## Panics
+    /// This will panic if time_scale is NaN, Infinity, or less than 0.
+    pub const fn new(fixed_step_nanos: u64, time_scale: f64) -> Time {
+        assert!(time_scale >= 0.0);
+        assert!(time_scale != f64::INFINITY);
+        Time {
+            delta_time: 0,
+            delta_real_time: 0,
+            previous_delta_real_time: 0,
+            frame_real_time: 0,
+            frame_time: 0,
+            fixed_time: fixed_step_nanos,
+            fixed_time_accumulator: 0,
+            frame_number: 0,
+            absolute_real_time: 0,
+            absolute_time: 0,
+            base_time_scale: time_scale,
+            base_scale_ratio: None,
+            scale_layers: BTreeMap::new(),
+            fixed_rates: BTreeMap::new(),
+            scale_ramp: None,
+            speed_presets: DEFAULT_SPEED_PRESETS,
+            speed_ramp_duration: 250_000_000,
+            scale_track: None,
+            track_scale: 1.0,
+            time_scale,
+            scale_ratio: ScaleRatio::from_f64(time_scale),
+            scale_remainder: 0,
+            rounding: DeltaRounding::Carry,
+            min_delta: 0,
+            catch_up: CatchUp::Immediate,
+            stepping_policy: None,
+            fixed_steps_this_frame: 0,
+            fixed_step_count: 0,
+            last_fixed_step_time: 0,
+            in_fixed_step: false,
+            fixed_phase: false,
+            require_drained_fixed_steps: false,
+            fixed_step_budget: u32::MAX,
+            partial_step: false,
+            fixed_step_length: fixed_step_nanos,
+            render_skip_threshold: None,
+            hitch_threshold: 100_000_000,
+            slow_frame_threshold: None,
+            slow_frame_hook: None,
+            suspend_detection: None,
+            halt_threshold: None,
+            capture: None,
+            frame_extras: false,
+            mutation_log_len: 0,
+            mutation_log: VecDeque::new(),
+            events: Vec::new(),
+            frame_start: None,
+            focused: true,
+            freeze_when_unfocused: false,
+            excluded_sections: 0,
+            section_opened: false,
+            frame_excluded: false,
+            excluded_time: 0,
+            frame_history_len: 0,
+            frame_history: VecDeque::new(),
+        }
+    }
+
+    /// Gets the time difference between frames, or the length of the fixed step in progress
+    /// while a `FixedUpdateGuard` is alive.
+    ///
+    /// In strict mode, this panics when called inside a fixed step run without a guard, where
+    /// `fixed_time` is the delta to use.
+    #[inline]
     pub fn delta_time(&self) -> Duration {
-        self.delta_time
+        if self.fixed_phase {
+            return Duration::from_nanos(self.fixed_step_length);
+        }
+        strict_assert(!self.in_fixed_step, "delta_time read inside a fixed step");
+        Duration::from_nanos(self.delta_time)
+    }
+
+    /// Gets the time difference between frames ignoring the time speed multiplier.
+    #[inline]
+    pub fn delta_real_time(&self) -> Duration {
+        Duration::from_nanos(self.delta_real_time)
+    }
+
+    /// Gets the fixed time step.
+    /// Must be used instead of delta_time during fixed updates.
+    #[inline]
+    pub fn fixed_time(&self) -> Duration {
+        Duration::from_nanos(self.fixed_time)
+    }
+
+    /// Gets the current frame number.  This increments by 1 every frame.  There is no frame 0.
+    #[inline]
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+
+    /// Gets the time since the start of the game, taking into account the speed multiplier.
+    #[inline]
+    pub fn absolute_time(&self) -> Duration {
+        Duration::from_nanos(self.absolute_time)
+    }
+
+    /// Gets the time since the start of the game, ignoring the speed multiplier.
+    #[inline]
+    pub fn absolute_real_time(&self) -> Duration {
+        Duration::from_nanos(self.absolute_real_time)
+    }
+
+    /// Gets the current point on the scaled game timeline.
+    pub fn game_now(&self) -> GameInstant {
+        GameInstant::from_start(self.absolute_time())
+    }
+
+    /// Gets the current point on the scaled game timeline, in its stored form for save files.
+    pub fn timestamp(&self) -> GameTimestamp {
+        GameTimestamp::from_nanos(self.absolute_time)
+    }
+
+    /// Gets the current point on the real timeline.
+    pub fn real_now(&self) -> RealInstant {
+        RealInstant::from_start(self.absolute_real_time())
+    }
+
+    /// Gets the scaled game time elapsed during the frame.
+    pub fn game_delta(&self) -> GameDuration {
+        GameDuration::new(self.delta_time())
+    }
+
+    /// Gets the real time elapsed during the frame.
+    pub fn real_delta(&self) -> RealDuration {
+        RealDuration::new(self.delta_real_time())
+    }
+
+    /// Packs the frequently read values of the clock into plain integers,
+    /// for example to share them with other threads through a `SharedTime`.
+    #[inline]
+    pub fn pack(&self) -> PackedTime {
+        PackedTime {
+            delta_nanos: self.delta_time,
+            delta_real_nanos: self.delta_real_time,
+            absolute_nanos: self.absolute_time,
+            absolute_real_nanos: self.absolute_real_time,
+            frame_number: self.frame_number,
+        }
+    }
+
+    /// Gets a read-only view of the clock, to hand to scripts and mods.
+    pub fn view(&self) -> TimeView<'_> {
+        TimeView::new(self)
+    }
+
+    /// Gets the current time speed multiplier, including every scale layer.
+    #[inline]
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale as f32
+    }
+
+    /// Gets the current time speed multiplier, including every scale layer, in full precision.
+    pub fn time_scale_f64(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Gets the ratio used to compute scaled time from real time, as
+    /// `(numerator, denominator)` in lowest terms.
+    pub fn time_scale_ratio(&self) -> (u64, u64) {
+        let ratio = ScaleRatio::reduced(
+            u128::from(self.scale_ratio.numerator),
+            u128::from(self.scale_ratio.denominator),
+        )
+        .unwrap_or(self.scale_ratio);
+        (ratio.numerator, ratio.denominator)
+    }
+
+    /// Gets the time speed multiplier set by `set_time_scale`, ignoring scale layers.
+    pub fn base_time_scale(&self) -> f32 {
+        self.base_time_scale as f32
+    }
+
+    /// Gets the multiplier of the named scale layer, if it is set.
+    pub fn scale_layer(&self, name: &str) -> Option<f32> {
+        self.scale_layers.get(name).copied()
+    }
+
+    /// Gets the scaled delta time of the frame, further multiplied by `multiplier`.
+    ///
+    /// This is the delta of an entity slowed down or sped up on its own (status effects),
+    /// on top of the time scale of the whole game.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn scaled_delta_for(&self, multiplier: f32) -> Duration {
+        assert!(multiplier >= 0.0);
+        assert!(multiplier != f32::INFINITY);
+        Duration::from_nanos(ScaleRatio::from_f64(f64::from(multiplier)).apply(self.delta_time))
+    }
+
+    /// Converts a duration of real time into the game time that passes during it at the current
+    /// time scale.
+    pub fn to_scaled(&self, real: Duration) -> Duration {
+        Duration::from_nanos(self.scale_ratio.apply(to_nanos(real)))
+    }
+
+    /// Converts a duration of game time into the real time needed for it to pass at the current
+    /// time scale.
+    ///
+    /// While time is stopped (scale of 0), game time never passes: any non-zero duration
+    /// converts to `Duration::MAX`.
+    pub fn to_real(&self, scaled: Duration) -> Duration {
+        if scaled == Duration::from_secs(0) {
+            return scaled;
+        }
+        if self.scale_ratio.numerator == 0 {
+            return Duration::MAX;
+        }
+        Duration::from_nanos(self.scale_ratio.invert(to_nanos(scaled)))
+    }
+
+    /// Gets the smallest frame delta that `advance_frame` will report.
+    pub fn min_delta(&self) -> Duration {
+        Duration::from_nanos(self.min_delta)
+    }
+
+    /// Sets delta_time to the given `Duration`.
+    /// Updates the struct to reflect the changes of this frame.
+    /// This should be called before using step_fixed_update.
+    ///
+    /// If a minimum delta is set, shorter frames are reported as lasting the minimum delta,
+    /// while the absolute times and the fixed steps keep following the real delta.
+    /// If suspend detection is enabled, longer frames are handled according to its policy.
+    /// If halt detection is enabled, frames longer than the halt threshold repeat the previous
+    /// real delta.
+    ///
+    /// In strict mode, this panics if `set_require_drained_fixed_steps` is enabled and fixed
+    /// steps of the previous frame are still due.
+    pub fn advance_frame(&mut self, time_diff: Duration) {
+        strict_assert(
+            !self.require_drained_fixed_steps || !self.fixed_step_available(),
+            "advance_frame called before running the due fixed steps",
+        );
+        self.in_fixed_step = false;
+        self.events.clear();
+        let (measured, time_diff) = if self.frame_extras {
+            self.measure_delta(to_nanos(time_diff))
+        } else {
+            let time_diff = to_nanos(time_diff);
+            (time_diff, time_diff)
+        };
+        let clamped = time_diff != measured;
+        self.previous_delta_real_time = self.delta_real_time;
+        self.frame_excluded = self.section_opened;
+        self.section_opened = self.excluded_sections > 0;
+        let scaled_diff = if self.frame_excluded {
+            self.excluded_time += time_diff;
+            0
+        } else {
+            match self.rounding {
+                DeltaRounding::Truncate => self.scale_ratio.apply(time_diff),
+                DeltaRounding::Nearest => self.scale_ratio.apply_nearest(time_diff),
+                DeltaRounding::Carry => self
+                    .scale_ratio
+                    .apply_carrying(time_diff, &mut self.scale_remainder),
+            }
+        };
+        // The minimum delta only changes the reported deltas: the absolute times and the
+        // fixed steps follow the time that really passed.
+        if time_diff < self.min_delta {
+            self.delta_real_time = self.min_delta;
+            self.delta_time = if self.frame_excluded {
+                0
+            } else {
+                self.scale_ratio.apply(self.min_delta)
+            };
+        } else {
+            self.delta_real_time = time_diff;
+            self.delta_time = scaled_diff;
+        }
+        self.frame_number += 1;
+        if clamped {
+            self.log_mutation(ClockMutation::DeltaClamped {
+                measured: Duration::from_nanos(measured),
+                used: self.delta_real_time(),
+            });
+        }
+
+        self.frame_time = scaled_diff;
+        self.frame_real_time = time_diff;
+        self.absolute_time += scaled_diff;
+        self.absolute_real_time += time_diff;
+        if !self.is_frozen() && !self.frame_excluded {
+            self.fixed_time_accumulator += time_diff;
+        }
+        self.fixed_steps_this_frame = 0;
+        let plan = self.step_plan();
+        let discard = to_nanos(plan.discard).min(self.fixed_time_accumulator);
+        if discard > 0 {
+            self.fixed_time_accumulator -= discard;
+            self.log_mutation(ClockMutation::StepsDropped {
+                time: Duration::from_nanos(discard),
+            });
+        }
+        self.fixed_step_budget = plan.max_steps;
+        self.partial_step = plan.partial_step;
+        if self.frame_extras {
+            self.advance_frame_extras(time_diff);
+        }
+    }
+
+    /// Applies the capture mode, then suspend and halt detection, to the real delta given to
+    /// `advance_frame`. Returns the delta before and after the detection.
+    #[inline(never)]
+    fn measure_delta(&mut self, mut time_diff: u64) -> (u64, u64) {
+        if let Some((fps, frames)) = self.capture {
+            let timestamp = |frame: u64| u128::from(frame) * 1_000_000_000 / u128::from(fps);
+            time_diff = (timestamp(frames + 1) - timestamp(frames)) as u64;
+            self.capture = Some((fps, frames + 1));
+        }
+        let measured = time_diff;
+        let gap = Duration::from_nanos(measured);
+        match (self.suspend_detection, self.halt_threshold) {
+            (Some((threshold, policy)), _) if time_diff > to_nanos(threshold) => {
+                self.events.push(ClockEvent::Resumed { gap });
+                time_diff = match policy {
+                    SuspendPolicy::Keep => time_diff,
+                    SuspendPolicy::Discard => 0,
+                    SuspendPolicy::Clamp(max) => time_diff.min(to_nanos(max)),
+                };
+            }
+            (_, Some(threshold)) if time_diff > threshold => {
+                self.events.push(ClockEvent::Halted { gap });
+                time_diff = self.delta_real_time;
+            }
+            _ => {}
+        }
+        (measured, time_diff)
+    }
+
+    /// Runs the work of `advance_frame` for the optional features of the clock, once the
+    /// frame is planned.
+    #[inline(never)]
+    fn advance_frame_extras(&mut self, time_diff: u64) {
+        if !self.is_frozen() && !self.frame_excluded {
+            for rate in self.fixed_rates.values_mut() {
+                rate.accumulator += time_diff;
+            }
+        }
+        if self.frame_history_len > 0 {
+            if self.frame_history.len() == self.frame_history_len {
+                self.frame_history.pop_front();
+            }
+            self.frame_history
+                .push_back((self.absolute_time, self.absolute_real_time));
+        }
+        // Ramps and tracks change the scale every frame: only their start is logged.
+        if let Some(ramp) = self.scale_ramp {
+            let elapsed = self.absolute_real_time.saturating_sub(ramp.start);
+            self.base_time_scale = if elapsed >= ramp.duration {
+                self.scale_ramp = None;
+                ramp.to
+            } else {
+                let t = ramp.easing.apply(elapsed as f32 / ramp.duration as f32);
+                ramp.from + (ramp.to - ramp.from) * f64::from(t)
+            };
+            self.base_scale_ratio = None;
+            self.compute_time_scale(false);
+        }
+        if let Some((track, start)) = &self.scale_track {
+            let elapsed = Duration::from_nanos(self.absolute_real_time.saturating_sub(*start));
+            let track_scale = track.sample(elapsed);
+            if track_scale != self.track_scale {
+                self.track_scale = track_scale;
+                self.compute_time_scale(false);
+            }
+        }
+
+        if let (Some(threshold), Some(hook)) = (self.slow_frame_threshold, &self.slow_frame_hook) {
+            if self.delta_real_time > to_nanos(threshold) {
+                hook.call(&SlowFrame {
+                    frame_number: self.frame_number,
+                    delta_real_time: self.delta_real_time(),
+                    threshold,
+                    steps_due: self.fixed_time_accumulator / self.fixed_time.max(1),
+                });
+            }
+        }
+        self.update_frame_extras();
+    }
+
+    /// Updates `frame_extras` after an optional feature was enabled or disabled.
+    fn update_frame_extras(&mut self) {
+        self.frame_extras = self.capture.is_some()
+            || self.suspend_detection.is_some()
+            || self.halt_threshold.is_some()
+            || !self.fixed_rates.is_empty()
+            || self.frame_history_len > 0
+            || self.scale_ramp.is_some()
+            || self.scale_track.is_some()
+            || self.slow_frame_hook.is_some();
+    }
+
+    /// Advances the frame by the time elapsed since the instant given to the previous call.
+    /// The first call advances by zero.
+    ///
+    /// An instant that is not after the previous one advances the frame by zero (raised to the
+    /// minimum delta if one is set) and reports a `ClockEvent::NonMonotonic`, instead of
+    /// letting zero or negative deltas reach the game. The frame start stays at the previous,
+    /// later instant, from which the next delta is measured.
+    pub fn advance_frame_at(&mut self, now: Instant) {
+        // A backwards instant keeps the later frame start, so that no time is counted twice.
+        let (delta, backwards) = match self.frame_start {
+            Some(last) if now <= last => (Duration::from_secs(0), Some(last - now)),
+            Some(last) => (now - last, None),
+            None => (Duration::from_secs(0), None),
+        };
+        if backwards.is_none() {
+            self.frame_start = Some(now);
+        }
+        self.advance_frame(delta);
+        if let Some(backwards) = backwards {
+            self.events.push(ClockEvent::NonMonotonic { backwards });
+        }
+    }
+
+    /// Advances the frame by the time elapsed since the previous call, measured with
+    /// `Instant::now`. See `advance_frame_at`.
+    pub fn advance_frame_now(&mut self) {
+        self.advance_frame_at(Instant::now());
+    }
+
+    /// Gets the instant at which the current frame began: the latest one given to
+    /// `advance_frame_at` or `advance_frame_now`. `None` if the clock is advanced with
+    /// durations instead.
+    pub fn frame_start(&self) -> Option<Instant> {
+        self.frame_start
+    }
+
+    /// Gets the real time elapsed since the current frame began, for budgeting work within
+    /// the frame. `None` if the clock is advanced with durations instead.
+    pub fn time_since_frame_start(&self) -> Option<Duration> {
+        self.time_since_frame_start_from(&SystemClock)
+    }
+
+    /// Like `time_since_frame_start`, reading the current time from `source`.
+    pub fn time_since_frame_start_from(&self, source: &impl ClockSource) -> Option<Duration> {
+        self.frame_start
+            .map(|start| source.now().saturating_duration_since(start))
+    }
+
+    /// Moves a point of the real timeline by the real time between the frame start and
+    /// `instant`. `None` if the frame start is unknown.
+    fn offset_from_frame_start(
+        &self,
+        instant: Instant,
+        nanos: u64,
+        scale: impl Fn(u64) -> u64,
+    ) -> Option<u64> {
+        let start = self.frame_start?;
+        Some(if instant >= start {
+            nanos.saturating_add(scale(to_nanos(instant - start)))
+        } else {
+            nanos.saturating_sub(scale(to_nanos(start - instant)))
+        })
+    }
+
+    /// Maps an `Instant`, like the timestamp of an input event captured by the windowing
+    /// library, onto the real timeline of the clock. `None` unless the clock is advanced with
+    /// `advance_frame_at` or `advance_frame_now`.
+    pub fn real_time_at(&self, instant: Instant) -> Option<Duration> {
+        self.offset_from_frame_start(instant, self.absolute_real_time, |nanos| nanos)
+            .map(Duration::from_nanos)
+    }
+
+    /// Maps an `Instant` onto the scaled timeline of the clock, assuming the current time
+    /// scale applied since then. `None` unless the clock is advanced with `advance_frame_at`
+    /// or `advance_frame_now`.
+    pub fn game_time_at(&self, instant: Instant) -> Option<Duration> {
+        self.offset_from_frame_start(instant, self.absolute_time, |nanos| {
+            self.scale_ratio.apply(nanos)
+        })
+        .map(Duration::from_nanos)
+    }
+
+    /// Gets the fixed tick, numbered like `fixed_step_count`, that should consume an input
+    /// captured at `instant`: the first tick simulating past that moment. Consuming input at
+    /// that tick instead of the next one run keeps replays and netcode fair whatever the
+    /// frame rate. `None` unless the clock is advanced with `advance_frame_at` or
+    /// `advance_frame_now`, or if the fixed time step is zero.
+    pub fn fixed_tick_at(&self, instant: Instant) -> Option<u64> {
+        if self.fixed_time == 0 {
+            return None;
+        }
+        let simulated = self.last_fixed_step_time + self.fixed_time_accumulator;
+        self.offset_from_frame_start(instant, simulated, |nanos| nanos)
+            .map(|position| position / self.fixed_time + 1)
+    }
+
+    /// Advances the frame so that `absolute_real_time` becomes `target_absolute_real_time`.
+    ///
+    /// Useful when an external authority (a server, a video encoder, a replay file) dictates the
+    /// timeline in absolute terms. A target earlier than the current time advances by zero.
+    pub fn advance_to(&mut self, target_absolute_real_time: Duration) {
+        let time_diff = to_nanos(target_absolute_real_time).saturating_sub(self.absolute_real_time);
+        self.advance_frame(Duration::from_nanos(time_diff));
+    }
+
+    /// Advances the clock by `duration` as fast as possible, calling `on_fixed_step` for every
+    /// fixed step that becomes due, and returns the number of fixed steps run.
+    ///
+    /// The duration is played as frames lasting one fixed time step each, so `on_fixed_step`
+    /// sees the same values as during regular play. Use this to catch up on offline time,
+    /// fast-forward a server after loading, or run minutes of simulation in a test.
+    pub fn simulate(&mut self, duration: Duration, mut on_fixed_step: impl FnMut(&Time)) -> u64 {
+        let mut remaining = to_nanos(duration);
+        let mut steps = 0;
+        loop {
+            let frame = if self.fixed_time == 0 {
+                remaining
+            } else {
+                remaining.min(self.fixed_time)
+            };
+            self.advance_frame(Duration::from_nanos(frame));
+            while self.step_fixed_update() {
+                on_fixed_step(self);
+                steps += 1;
+            }
+            remaining -= frame;
+            if remaining == 0 {
+                return steps;
+            }
+        }
+    }
+
+    /// Sets both `fixed_time` and `fixed_seconds` based on the duration given.
+    pub fn set_fixed_time(&mut self, time: Duration) {
+        if to_nanos(time) != self.fixed_time {
+            self.log_mutation(ClockMutation::FixedTime {
+                from: self.fixed_time(),
+                to: time,
+            });
+        }
+        self.fixed_time = to_nanos(time);
+        self.replan();
+    }
+
+    /// Changes the fixed time step mid-session, for example to 30Hz during cutscenes and
+    /// 120Hz during gameplay, converting the accumulator according to `policy`.
+    /// `set_fixed_time` is the same as `RateChangePolicy::Keep`.
+    ///
+    /// `fixed_ticks_between` assumes a constant step, and doesn't account for the change.
+    ///
+    /// ## Panics
+    /// This will panic if the fixed time step is zero.
+    pub fn change_fixed_time(&mut self, time: Duration, policy: RateChangePolicy) -> RateChange {
+        let new = to_nanos(time);
+        assert!(new > 0);
+        let old = self.fixed_time;
+        let before = self.fixed_time_accumulator;
+        let after = match policy {
+            RateChangePolicy::Keep => before,
+            RateChangePolicy::Proportional => {
+                (u128::from(before) * u128::from(new) / u128::from(old.max(1))) as u64
+            }
+            RateChangePolicy::Flush => 0,
+        };
+        self.fixed_time_accumulator = after;
+        if after < before {
+            self.log_mutation(ClockMutation::StepsDropped {
+                time: Duration::from_nanos(before - after),
+            });
+        }
+        self.set_fixed_time(time);
+        RateChange {
+            first_tick: self.fixed_step_count + 1,
+            steps_due_before: before / old.max(1),
+            steps_due_after: after / new,
+            discarded: Duration::from_nanos(before.saturating_sub(after)),
+        }
+    }
+
+    /// Sets the smallest frame delta that will be reported.
+    ///
+    /// Very fast frames (menus running at hundreds of frames per second) can otherwise produce
+    /// deltas small enough to destabilize divisions and normalizations done with delta_time.
+    /// Use `Duration::from_secs(0)` to disable the clamp.
+    pub fn set_min_delta(&mut self, min_delta: Duration) {
+        self.min_delta = to_nanos(min_delta);
+    }
+
+    /// Gets the number of fixed steps run since the last call to `advance_frame`.
+    pub fn fixed_steps_this_frame(&self) -> u32 {
+        self.fixed_steps_this_frame
+    }
+
+    /// Gets the number of fixed steps run since the clock was created.
+    ///
+    /// This is the simulation tick, which physics and netcode should key their state to
+    /// instead of the frame number.
+    pub fn fixed_step_count(&self) -> u64 {
+        self.fixed_step_count
+    }
+
+    /// Gets the simulation time reached by the last fixed step: the sum of the fixed time
+    /// steps run since the clock was created.
+    pub fn last_fixed_step_time(&self) -> Duration {
+        Duration::from_nanos(self.last_fixed_step_time)
+    }
+
+    /// Adds a secondary fixed time step under the given name, for systems updating at their
+    /// own cadence, like a 10 Hz AI next to 60 Hz physics. Its accumulator fills like the main
+    /// one. Changing the step of an existing rate keeps its accumulator.
+    ///
+    /// Secondary rates ignore the catch-up policy: every due step runs.
+    ///
+    /// ## Panics
+    /// This will panic if step is zero.
+    pub fn set_fixed_rate(&mut self, name: impl Into<String>, step: Duration) {
+        assert!(step > Duration::from_secs(0));
+        let step = to_nanos(step);
+        self.fixed_rates
+            .entry(name.into())
+            .and_modify(|rate| rate.step = step)
+            .or_insert(FixedRate {
+                step,
+                accumulator: 0,
+                last_step_time: 0,
+            });
+        self.update_frame_extras();
+    }
+
+    /// Removes the named secondary fixed time step. Does nothing if it isn't set.
+    pub fn remove_fixed_rate(&mut self, name: &str) {
+        self.fixed_rates.remove(name);
+        self.update_frame_extras();
+    }
+
+    /// Gets the named secondary fixed time step, if it is set.
+    pub fn fixed_rate(&self, name: &str) -> Option<Duration> {
+        self.fixed_rates
+            .get(name)
+            .map(|rate| Duration::from_nanos(rate.step))
+    }
+
+    /// Like `step_fixed_update`, for the named secondary fixed time step.
+    /// Returns false if it isn't set.
+    pub fn step_fixed_rate(&mut self, name: &str) -> bool {
+        match self.fixed_rates.get_mut(name) {
+            Some(rate) if rate.accumulator >= rate.step => {
+                rate.accumulator -= rate.step;
+                rate.last_step_time += rate.step;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Like `fixed_step_alpha`, for the named secondary fixed time step.
+    pub fn fixed_rate_alpha(&self, name: &str) -> Option<f64> {
+        self.fixed_rates
+            .get(name)
+            .map(|rate| rate.accumulator.min(rate.step) as f64 / rate.step as f64)
+    }
+
+    /// Like `last_fixed_step_time`, for the named secondary fixed time step.
+    pub fn last_fixed_rate_step_time(&self, name: &str) -> Option<Duration> {
+        self.fixed_rates
+            .get(name)
+            .map(|rate| Duration::from_nanos(rate.last_step_time))
+    }
+
+    /// Gets the fixed ticks that ended at a simulation time in `[start, end)`, numbered like
+    /// `fixed_step_count`: tick `n` is the `n`th fixed step and ends at `n` fixed time steps.
+    ///
+    /// This assumes the fixed time step never changed. Lag compensation can use it to find
+    /// the ticks a late input or a back-dated projectile spans. Empty if the fixed time step
+    /// is zero.
+    pub fn fixed_ticks_between(&self, start: Duration, end: Duration) -> Range<u64> {
+        if self.fixed_time == 0 {
+            return 0..0;
+        }
+        let first = to_nanos(start).div_ceil(self.fixed_time).max(1);
+        let last = to_nanos(end).div_ceil(self.fixed_time).max(first);
+        first..last
+    }
+
+    /// Gets a snapshot of the timing state of the clock, to store in a save or replay file.
+    ///
+    /// Configuration (catch-up policy, thresholds, hooks, scale layers...) is not part of the
+    /// state, and neither is the multiplier of the scale layers.
+    pub fn state(&self) -> TimeStateLatest {
+        let base = self
+            .base_scale_ratio
+            .unwrap_or_else(|| ScaleRatio::from_f64(self.base_time_scale));
+        let base = ScaleRatio::reduced(u128::from(base.numerator), u128::from(base.denominator))
+            .unwrap_or(base);
+        let scale_remainder = u128::from(self.scale_remainder) * u128::from(base.denominator)
+            / u128::from(self.scale_ratio.denominator);
+        TimeStateV2 {
+            delta_nanos: self.delta_time,
+            delta_real_nanos: self.delta_real_time,
+            fixed_nanos: self.fixed_time,
+            frame_number: self.frame_number,
+            absolute_real_nanos: self.absolute_real_time,
+            absolute_nanos: self.absolute_time,
+            time_scale_numerator: base.numerator,
+            time_scale_denominator: base.denominator,
+            scale_remainder: scale_remainder as u64,
+            fixed_accumulator_nanos: self.fixed_time_accumulator,
+            fixed_step_count: self.fixed_step_count,
+            last_fixed_step_nanos: self.last_fixed_step_time,
+        }
+    }
+
+    /// Restores a state returned by `state`, possibly by an older version of the crate.
+    ///
+    /// The times, counters, accumulator, time scale and fixed time step of the state are
+    /// restored. A fixed time step of zero, as migrated from a default `TimeStateV1`, is
+    /// ignored and the current one is kept. The frame history, scale ramp and scale track
+    /// refer to the replaced timeline and are cleared. The rest of the configuration of the
+    /// clock, like scale layers and the catch-up policy, is kept.
+    ///
+    /// ## Panics
+    /// This will panic if the denominator of the time scale is 0.
+    pub fn restore_state(&mut self, state: impl Into<TimeState>) {
+        let state = state.into().migrate();
+        self.delta_time = state.delta_nanos;
+        self.delta_real_time = state.delta_real_nanos;
+        self.previous_delta_real_time = state.delta_real_nanos;
+        self.frame_number = state.frame_number;
+        self.absolute_real_time = state.absolute_real_nanos;
+        self.absolute_time = state.absolute_nanos;
+        self.frame_real_time = state.delta_real_nanos.min(state.absolute_real_nanos);
+        self.frame_time = state.delta_nanos.min(state.absolute_nanos);
+        self.fixed_time_accumulator = state.fixed_accumulator_nanos;
+        self.fixed_step_count = state.fixed_step_count;
+        self.last_fixed_step_time = state.last_fixed_step_nanos;
+        self.fixed_steps_this_frame = 0;
+        if state.fixed_nanos > 0 {
+            self.fixed_time = state.fixed_nanos;
+        }
+        self.frame_history.clear();
+        self.scale_track = None;
+        self.update_frame_extras();
+        self.track_scale = 1.0;
+        // Also cancels the scale ramp.
+        self.set_time_scale_ratio(state.time_scale_numerator, state.time_scale_denominator);
+        self.scale_remainder = (u128::from(state.scale_remainder)
+            * u128::from(self.scale_ratio.denominator)
+            / u128::from(state.time_scale_denominator)) as u64;
+        self.replan();
+    }
+
+    /// Gets how far the simulation is between the last fixed step and the next one, from 0 to 1.
+    /// Call this after running the fixed updates of the frame, and use it to interpolate
+    /// rendered state between the two last fixed steps.
+    pub fn fixed_step_alpha(&self) -> f64 {
+        if self.fixed_time == 0 {
+            return 0.0;
+        }
+        self.fixed_time_accumulator.min(self.fixed_time) as f64 / self.fixed_time as f64
+    }
+
+    /// Gets the simulation time to render at: `last_fixed_step_time` plus `fixed_step_alpha`
+    /// times the fixed time step. Sample animations at this time to keep them in step with
+    /// interpolated fixed-step state.
+    pub fn interpolated_time(&self) -> Duration {
+        Duration::from_nanos(
+            self.last_fixed_step_time + self.fixed_time_accumulator.min(self.fixed_time),
+        )
+    }
+
+    /// Hashes the deterministic state of the clock: the fixed step count, fixed time step,
+    /// accumulator, scaled absolute time and exact time scale.
+    ///
+    /// Lockstep peers feeding the same deltas can compare fingerprints every tick to detect
+    /// desyncs caused by timing rather than by game logic. The hash is the same on every
+    /// platform.
+    pub fn tick_fingerprint(&self) -> u64 {
+        let (numerator, denominator) = self.time_scale_ratio();
+        fnv1a(&[
+            self.fixed_step_count,
+            self.fixed_time,
+            self.fixed_time_accumulator,
+            self.absolute_time,
+            numerator,
+            denominator,
+        ])
+    }
+
+    /// Gets the real frame delta above which a frame counts as a hitch.
+    pub fn hitch_threshold(&self) -> Duration {
+        Duration::from_nanos(self.hitch_threshold)
+    }
+
+    /// Sets the real frame delta above which a frame counts as a hitch. Defaults to 100ms.
+    pub fn set_hitch_threshold(&mut self, threshold: Duration) {
+        self.hitch_threshold = to_nanos(threshold);
+    }
+
+    /// Checks if the current frame took longer than the hitch threshold.
+    pub fn is_hitch(&self) -> bool {
+        self.delta_real_time > self.hitch_threshold
+    }
+
+    /// Calls `hook` from `advance_frame` whenever a frame takes longer than `threshold`,
+    /// for example to log a warning giving context to stutter reports.
+    ///
+    /// The hook can capture state, like a logger or a channel. Clones of this clock share it.
+    pub fn set_slow_frame_hook(
+        &mut self,
+        threshold: Duration,
+        hook: impl FnMut(&SlowFrame) + Send + 'static,
+    ) {
+        self.slow_frame_threshold = Some(threshold);
+        self.slow_frame_hook = Some(Hook(Arc::new(Mutex::new(hook))));
+        self.update_frame_extras();
+    }
+
+    /// Removes the hook set with `set_slow_frame_hook`.
+    pub fn clear_slow_frame_hook(&mut self) {
+        self.slow_frame_threshold = None;
+        self.slow_frame_hook = None;
+        self.update_frame_extras();
+    }
+
+    /// Gathers the values worth displaying in a debug overlay into a single struct.
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        let fps = if self.delta_real_time == 0 {
+            0.0
+        } else {
+            1_000_000_000.0 / self.delta_real_time as f64
+        };
+        DebugSnapshot {
+            frame_number: self.frame_number,
+            fps,
+            delta_time: Duration::from_nanos(self.delta_time),
+            delta_real_time: Duration::from_nanos(self.delta_real_time),
+            time_scale: self.time_scale,
+            accumulator_fill: self.fixed_time_accumulator as f64 / self.fixed_time.max(1) as f64,
+            fixed_steps: self.fixed_steps_this_frame,
+            hitch: self.is_hitch(),
+        }
+    }
+
+    /// Gets the events detected during the current frame.
+    pub fn events(&self) -> &[ClockEvent] {
+        &self.events
+    }
+
+    /// Gets the suspend threshold and policy, if suspend detection is enabled.
+    pub fn suspend_detection(&self) -> Option<(Duration, SuspendPolicy)> {
+        self.suspend_detection
+    }
+
+    /// Treats real deltas longer than `threshold` as the machine having slept or the app
+    /// having been suspended: a `ClockEvent::Resumed` is reported and the delta is handled
+    /// according to `policy`.
+    pub fn set_suspend_detection(&mut self, threshold: Duration, policy: SuspendPolicy) {
+        self.suspend_detection = Some((threshold, policy));
+        self.update_frame_extras();
+    }
+
+    /// Disables suspend detection. Every real delta is then counted in full.
+    pub fn clear_suspend_detection(&mut self) {
+        self.suspend_detection = None;
+        self.update_frame_extras();
+    }
+
+    /// Checks if the app window has focus, as last reported with `set_focused`.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Reports whether the app window has focus. Call this from the window focus events.
+    ///
+    /// While unfocused with `freeze_when_unfocused` enabled, scaled time stops and no fixed
+    /// steps become due. Real time keeps advancing. Everything resumes where it stopped when
+    /// focus comes back. Pair this with `FrameLimiter::set_focused` to also lower the frame rate.
+    pub fn set_focused(&mut self, focused: bool) {
+        if self.focused != focused {
+            self.focused = focused;
+            self.update_time_scale();
+        }
+    }
+
+    /// Checks if scaled time and fixed steps stop while the app is unfocused.
+    pub fn freeze_when_unfocused(&self) -> bool {
+        self.freeze_when_unfocused
+    }
+
+    /// Sets whether scaled time and fixed steps stop while the app is unfocused.
+    /// Disabled by default.
+    pub fn set_freeze_when_unfocused(&mut self, freeze: bool) {
+        if self.freeze_when_unfocused != freeze {
+            self.freeze_when_unfocused = freeze;
+            self.update_time_scale();
+        }
+    }
+
+    /// Starts a section excluded from scaled time, like a loading screen. Sections can nest.
+    ///
+    /// Every frame during which a section is open, even briefly, is excluded in full: its
+    /// scaled delta is zero and no fixed steps become due, while real time keeps advancing.
+    /// A blocking load between `begin_excluded_section` and `end_excluded_section` is thus
+    /// excluded from the next frame.
+    pub fn begin_excluded_section(&mut self) {
+        self.excluded_sections += 1;
+        self.section_opened = true;
+    }
+
+    /// Ends the innermost section started with `begin_excluded_section`.
+    ///
+    /// In strict mode, this panics if no section is open.
+    pub fn end_excluded_section(&mut self) {
+        strict_assert(
+            self.excluded_sections > 0,
+            "end_excluded_section called without an open section",
+        );
+        self.excluded_sections = self.excluded_sections.saturating_sub(1);
+    }
+
+    /// Checks if an excluded section is open.
+    pub fn in_excluded_section(&self) -> bool {
+        self.excluded_sections > 0
+    }
+
+    /// Checks if the current frame was excluded from scaled time by an excluded section.
+    pub fn is_frame_excluded(&self) -> bool {
+        self.frame_excluded
+    }
+
+    /// Gets the total real time of the frames excluded by excluded sections, for example to
+    /// subtract loading times from a speedrun timer.
+    pub fn excluded_time(&self) -> Duration {
+        Duration::from_nanos(self.excluded_time)
+    }
+
+    /// Checks if time is currently stopped because the app is unfocused.
+    fn is_frozen(&self) -> bool {
+        !self.focused && self.freeze_when_unfocused
+    }
+
+    /// Gets the number of recent frames whose absolute times are remembered.
+    pub fn frame_history_len(&self) -> usize {
+        self.frame_history_len
+    }
+
+    /// Sets the number of recent frames whose absolute times are remembered, for replay
+    /// tooling and lag compensation. Defaults to 0, which disables the history.
+    pub fn set_frame_history_len(&mut self, len: usize) {
+        self.frame_history_len = len;
+        self.update_frame_extras();
+        while self.frame_history.len() > len {
+            self.frame_history.pop_front();
+        }
+    }
+
+    /// Gets the scaled absolute time at the end of the given frame,
+    /// if the frame is still in the history.
+    pub fn absolute_time_of_frame(&self, frame_number: u64) -> Option<Duration> {
+        self.frame_history_entry(frame_number)
+            .map(|(absolute, _)| Duration::from_nanos(absolute))
+    }
+
+    /// Gets the real absolute time at the end of the given frame,
+    /// if the frame is still in the history.
+    pub fn absolute_real_time_of_frame(&self, frame_number: u64) -> Option<Duration> {
+        self.frame_history_entry(frame_number)
+            .map(|(_, absolute_real)| Duration::from_nanos(absolute_real))
+    }
+
+    /// Gets the frames of the history that ended at a scaled absolute time in `[start, end)`.
+    pub fn frames_between(&self, start: Duration, end: Duration) -> Range<u64> {
+        let first_frame = (self.frame_number + 1).saturating_sub(self.frame_history.len() as u64);
+        let (start, end) = (to_nanos(start), to_nanos(end));
+        let first = self
+            .frame_history
+            .partition_point(|(absolute, _)| *absolute < start);
+        let last = self
+            .frame_history
+            .partition_point(|(absolute, _)| *absolute < end)
+            .max(first);
+        first_frame + first as u64..first_frame + last as u64
+    }
+
+    fn frame_history_entry(&self, frame_number: u64) -> Option<(u64, u64)> {
+        let back = self.frame_number.checked_sub(frame_number)?;
+        let index = (self.frame_history.len() as u64).checked_sub(back + 1)?;
+        self.frame_history.get(index as usize).copied()
+    }
+
+    /// Gets the real delta above which execution is considered to have been halted.
+    pub fn halt_threshold(&self) -> Option<Duration> {
+        self.halt_threshold.map(Duration::from_nanos)
+    }
+
+    /// Treats a real delta longer than `threshold` (for example 1s) as execution having been
+    /// halted by a debugger breakpoint or a blocking OS dialog: a `ClockEvent::Halted` is
+    /// reported and the frame advances by the real delta of the previous frame instead, so
+    /// stepping through code doesn't launch the player across the map.
+    ///
+    /// Deltas above the suspend threshold are handled by suspend detection instead.
+    /// `None` disables halt detection.
+    pub fn set_halt_threshold(&mut self, threshold: Option<Duration>) {
+        self.halt_threshold = threshold.map(to_nanos);
+        self.update_frame_extras();
+    }
+
+    /// Gets the frame rate of the capture mode, if enabled.
+    pub fn capture_fps(&self) -> Option<u32> {
+        self.capture.map(|(fps, _)| fps)
+    }
+
+    /// Enables the capture mode, for video and GIF recording: every frame advances by
+    /// exactly `1 / fps` seconds whatever the delta given to `advance_frame`, so captured
+    /// frames get perfectly even timestamps. Frame `n` ends at `n / fps` seconds after the
+    /// capture started, rounded down to the nanosecond, so no rounding error builds up.
+    ///
+    /// To also pace rendering in real time, wait with a `FrameLimiter::new(fps)`.
+    /// `None` disables the capture mode.
+    ///
+    /// ## Panics
+    /// This will panic if fps is 0.
+    pub fn set_capture_fps(&mut self, fps: Option<u32>) {
+        assert!(fps != Some(0));
+        self.capture = fps.map(|fps| (fps, 0));
+        self.update_frame_extras();
+    }
+
+    /// Gets how scaled deltas are rounded.
+    pub fn rounding(&self) -> DeltaRounding {
+        self.rounding
+    }
+
+    /// Sets how scaled deltas are rounded. Defaults to `DeltaRounding::Carry`.
+    pub fn set_rounding(&mut self, rounding: DeltaRounding) {
+        self.rounding = rounding;
+        self.scale_remainder = 0;
+    }
+
+    /// Gets the policy used to drain the accumulator after a long frame.
+    pub fn catch_up(&self) -> CatchUp {
+        self.catch_up
+    }
+
+    /// Sets the policy used to drain the accumulator after a long frame.
+    pub fn set_catch_up(&mut self, catch_up: CatchUp) {
+        self.catch_up = catch_up;
+        self.replan();
+    }
+
+    /// Gets the accumulator fill above which `should_skip_render` returns true.
+    pub fn render_skip_threshold(&self) -> Option<Duration> {
+        self.render_skip_threshold
+    }
+
+    /// Sets the accumulator fill above which `should_skip_render` returns true.
+    /// `None` disables render skipping.
+    pub fn set_render_skip_threshold(&mut self, threshold: Option<Duration>) {
+        self.render_skip_threshold = threshold;
+    }
+
+    /// Checks if the simulation is far enough behind that this frame should not be rendered,
+    /// giving the fixed updates time to catch up.
+    /// Call this after running the fixed updates of the frame.
+    pub fn should_skip_render(&self) -> bool {
+        match self.render_skip_threshold {
+            Some(threshold) => self.fixed_time_accumulator > to_nanos(threshold),
+            None => false,
+        }
+    }
+
+    /// Sets the time multiplier that affects how time values are computed,
+    /// effectively slowing or speeding up your game.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn set_time_scale(&mut self, multiplier: f32) {
+        self.set_time_scale_f64(f64::from(multiplier));
+    }
+
+    /// Sets the time multiplier in full precision. See `set_time_scale`.
+    ///
+    /// Scaled time is computed from an exact ratio approximating the multiplier within 2^-32,
+    /// and the fraction of a nanosecond lost every frame is carried to the next one, so
+    /// scaled time never drifts away from `absolute_real_time` times that ratio. Multipliers
+    /// of 2^32 and above saturate to just under 2^32.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn set_time_scale_f64(&mut self, multiplier: f64) {
+        assert!(multiplier >= 0.0);
+        assert!(multiplier != f64::INFINITY);
+        self.scale_ramp = None;
+        self.update_frame_extras();
+        self.set_base_time_scale(multiplier);
+    }
+
+    fn set_base_time_scale(&mut self, multiplier: f64) {
+        self.base_time_scale = multiplier;
+        self.base_scale_ratio = None;
+        self.update_time_scale();
+    }
+
+    /// Moves the time multiplier to `target` over `duration` of real time, following
+    /// `easing`, instead of switching abruptly. Setting the time scale cancels the ramp.
+    ///
+    /// ## Panics
+    /// This will panic if target is NaN, Infinity, or less than 0.
+    pub fn ramp_time_scale(&mut self, target: f32, duration: Duration, easing: Easing) {
+        assert!(target >= 0.0);
+        assert!(target != f32::INFINITY);
+        self.scale_ramp = Some(ScaleRamp {
+            from: self.base_time_scale,
+            to: f64::from(target),
+            start: self.absolute_real_time,
+            duration: to_nanos(duration),
+            easing,
+        });
+        if duration == Duration::from_secs(0) {
+            self.scale_ramp = None;
+            self.set_base_time_scale(f64::from(target));
+        } else {
+            self.log_mutation(ClockMutation::RampStarted { target, duration });
+        }
+        self.update_frame_extras();
+    }
+
+    /// Gets the time multiplier the current ramp is moving to, if a ramp is in progress.
+    pub fn time_scale_target(&self) -> Option<f32> {
+        self.scale_ramp.map(|ramp| ramp.to as f32)
+    }
+
+    /// Gets the named time multipliers selectable with `set_speed_preset`.
+    pub fn speed_presets(&self) -> &'static [(&'static str, f32)] {
+        self.speed_presets
+    }
+
+    /// Sets the named time multipliers selectable with `set_speed_preset`.
+    /// Defaults to `DEFAULT_SPEED_PRESETS`.
+    pub fn set_speed_presets(&mut self, presets: &'static [(&'static str, f32)]) {
+        self.speed_presets = presets;
+    }
+
+    /// Gets the real time taken to switch between speed presets.
+    pub fn speed_ramp_duration(&self) -> Duration {
+        Duration::from_nanos(self.speed_ramp_duration)
+    }
+
+    /// Sets the real time taken to switch between speed presets. Defaults to 250ms.
+    pub fn set_speed_ramp_duration(&mut self, duration: Duration) {
+        self.speed_ramp_duration = to_nanos(duration);
+    }
+
+    /// Ramps the time multiplier to the named speed preset. Returns false, leaving the speed
+    /// unchanged, if there is no preset with that name.
+    pub fn set_speed_preset(&mut self, name: &str) -> bool {
+        match self
+            .speed_presets
+            .iter()
+            .find(|(preset, _)| *preset == name)
+        {
+            Some((_, scale)) => {
+                let duration = self.speed_ramp_duration();
+                self.ramp_time_scale(*scale, duration, Easing::EaseInOut);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gets the name of the speed preset the time multiplier is at, or ramping to.
+    pub fn speed_preset(&self) -> Option<&'static str> {
+        let target = self.scale_ramp.map_or(self.base_time_scale, |ramp| ramp.to);
+        self.speed_presets
+            .iter()
+            .find(|(_, scale)| f64::from(*scale) == target)
+            .map(|(name, _)| *name)
+    }
+
+    /// Sets the time multiplier to exactly `numerator / denominator`, for example `(1, 2)`
+    /// for half speed.
+    ///
+    /// Scaled time is then computed without any floating point, which deterministic
+    /// simulations need to stay in sync across machines. Scale layers set to values other
+    /// than 1 still go through an `f32` conversion.
+    ///
+    /// ## Panics
+    /// This will panic if denominator is 0.
+    pub fn set_time_scale_ratio(&mut self, numerator: u64, denominator: u64) {
+        assert!(denominator != 0);
+        self.scale_ramp = None;
+        self.update_frame_extras();
+        let ratio = ScaleRatio {
+            numerator,
+            denominator,
+        };
+        self.base_time_scale = ratio.to_f64();
+        self.base_scale_ratio = Some(ratio);
+        self.update_time_scale();
+    }
+
+    /// Sets a named time multiplier that is combined with the time scale and the other layers.
+    ///
+    /// Layers let independent systems (debug speed-up, gameplay slow motion, cutscenes)
+    /// change the speed of the game without overwriting each other's multiplier.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn set_scale_layer(&mut self, name: impl Into<String>, multiplier: f32) {
+        assert!(multiplier >= 0.0);
+        assert!(multiplier != f32::INFINITY);
+        self.scale_layers.insert(name.into(), multiplier);
+        self.update_time_scale();
     }
 
-    /// Gets the time difference between frames ignoring the time speed multiplier.
-    pub fn delta_real_time(&self) -> Duration {
-        self.delta_real_time
+    /// Removes the named scale layer. Does nothing if it isn't set.
+    pub fn clear_scale_layer(&mut self, name: &str) {
+        if self.scale_layers.remove(name).is_some() {
+            self.update_time_scale();
+        }
     }
 
-    /// Gets the fixed time step.
-    /// Must be used instead of delta_time during fixed updates.
-    pub fn fixed_time(&self) -> Duration {
-        self.fixed_time
+    /// Plays a scale track from the current time. The multiplier it gives is combined with
+    /// the time scale and the scale layers, and follows the track as real time advances.
+    /// Once the end of the track is reached, the multiplier stays at the scale of the last key
+    /// until the track is stopped.
+    pub fn play_scale_track(&mut self, track: ScaleTrack) {
+        self.log_mutation(ClockMutation::TrackStarted);
+        self.track_scale = track.sample(Duration::from_secs(0));
+        self.scale_track = Some((track, self.absolute_real_time));
+        self.update_frame_extras();
+        self.update_time_scale();
     }
 
-    /// Gets the current frame number.  This increments by 1 every frame.  There is no frame 0.
-    pub fn frame_number(&self) -> u64 {
-        self.frame_number
+    /// Gets the scale track being played.
+    pub fn scale_track(&self) -> Option<&ScaleTrack> {
+        self.scale_track.as_ref().map(|(track, _)| track)
     }
 
-    /// Gets the time since the start of the game, taking into account the speed multiplier.
-    pub fn absolute_time(&self) -> Duration {
-        self.absolute_time
+    /// Stops the scale track being played, removing its multiplier.
+    pub fn stop_scale_track(&mut self) {
+        self.scale_track = None;
+        self.update_frame_extras();
+        self.track_scale = 1.0;
+        self.update_time_scale();
     }
 
-    /// Gets the time since the start of the game, ignoring the speed multiplier.
-    pub fn absolute_real_time(&self) -> Duration {
-        self.absolute_real_time
+    fn update_time_scale(&mut self) {
+        self.compute_time_scale(true);
     }
 
-    /// Gets the current time speed multiplier.
-    pub fn time_scale(&self) -> f32 {
-        self.time_scale
+    /// Recomputes the time scale from its layers, logging the change if `log` is true.
+    fn compute_time_scale(&mut self, log: bool) {
+        let previous = self.time_scale;
+        let layers = self
+            .scale_layers
+            .values()
+            .chain(std::iter::once(&self.track_scale))
+            .map(|layer| f64::from(*layer))
+            .product::<f64>();
+        self.time_scale = layers * self.base_time_scale;
+        let mut ratio = match self.base_scale_ratio {
+            Some(base) if layers == 1.0 => Some(base),
+            Some(base) => base.checked_mul(ScaleRatio::from_f64(layers)),
+            None => None,
+        }
+        .unwrap_or_else(|| ScaleRatio::from_f64(self.time_scale));
+        if self.is_frozen() {
+            self.time_scale = 0.0;
+            ratio.numerator = 0;
+        }
+        // Keep the carried fraction of a nanosecond when the denominator changes.
+        self.scale_remainder = (u128::from(self.scale_remainder) * u128::from(ratio.denominator)
+            / u128::from(self.scale_ratio.denominator)) as u64;
+        self.scale_ratio = ratio;
+        if log && self.time_scale != previous {
+            self.log_mutation(match (previous == 0.0, self.time_scale == 0.0) {
+                (_, true) => ClockMutation::Paused,
+                (true, false) => ClockMutation::Unpaused {
+                    scale: self.time_scale,
+                },
+                (false, false) => ClockMutation::TimeScale {
+                    from: previous,
+                    to: self.time_scale,
+                },
+            });
+        }
     }
 
-    /// Sets delta_time to the given `Duration`.
-    /// Updates the struct to reflect the changes of this frame.
-    /// This should be called before using step_fixed_update.
-    pub fn advance_frame(&mut self, time_diff: Duration) {
-        self.delta_time = time_diff.clone().mul_f32(self.time_scale);
-        self.delta_real_time = time_diff;
-        self.frame_number += 1;
+    /// Gets the maximum number of entries kept in the mutation log.
+    pub fn mutation_log_len(&self) -> usize {
+        self.mutation_log_len
+    }
 
-        self.absolute_time += self.delta_time;
-        self.absolute_real_time += self.delta_real_time;
-        self.fixed_time_accumulator += self.delta_real_time;
+    /// Keeps the last `len` changes made to the clock (time scale changes, pauses, replaced
+    /// deltas, dropped fixed steps...) in a log, to find out what the clock was told to do
+    /// when a player reports that the game suddenly ran at double speed. Defaults to 0,
+    /// which disables the log.
+    pub fn set_mutation_log_len(&mut self, len: usize) {
+        self.mutation_log_len = len;
+        while self.mutation_log.len() > len {
+            self.mutation_log.pop_front();
+        }
     }
 
-    /// Sets both `fixed_time` and `fixed_seconds` based on the duration given.
-    pub fn set_fixed_time(&mut self, time: Duration) {
-        self.fixed_time = time;
+    /// Gets the recent changes made to the clock, oldest first.
+    pub fn mutation_log(&self) -> impl Iterator<Item = &LoggedMutation> {
+        self.mutation_log.iter()
     }
 
-    /// Sets the time multiplier that affects how time values are computed,
-    /// effectively slowing or speeding up your game.
-    ///
-    /// ## Panics
-    /// This will panic if multiplier is NaN, Infinity, or less than 0.
-    pub fn set_time_scale(&mut self, multiplier: f32) {
-        assert!(multiplier >= 0.0);
-        assert!(multiplier != std::f32::INFINITY);
-        self.time_scale = multiplier;
+    fn log_mutation(&mut self, mutation: ClockMutation) {
+        if self.mutation_log_len == 0 {
+            return;
+        }
+        if self.mutation_log.len() == self.mutation_log_len {
+            self.mutation_log.pop_front();
+        }
+        self.mutation_log.push_back(LoggedMutation {
+            frame_number: self.frame_number,
+            mutation,
+        });
     }
 
     /// Checks to see if we should perform another fixed update iteration, and if so, returns true
     /// and reduces the accumulator.
+    ///
+    /// With `CatchUp::Smooth`, this also returns false once the frame's step budget is spent,
+    /// leaving the remaining time in the accumulator for the next frames.
+    ///
+    /// In strict mode, this panics if no frame was advanced yet.
+    #[inline]
     pub fn step_fixed_update(&mut self) -> bool {
-        if self.fixed_time_accumulator >= self.fixed_time {
-            self.fixed_time_accumulator -= self.fixed_time;
+        strict_assert(
+            self.frame_number > 0,
+            "step_fixed_update called before advance_frame",
+        );
+        self.in_fixed_step = self.fixed_step_available();
+        if self.in_fixed_step {
+            self.fixed_step_length = self.fixed_time.min(self.fixed_time_accumulator);
+            self.fixed_time_accumulator -= self.fixed_step_length;
+            self.fixed_steps_this_frame += 1;
+            self.fixed_step_count += 1;
+            self.last_fixed_step_time += self.fixed_step_length;
             true
         } else {
             false
         }
     }
+
+    /// Like `step_fixed_update`, but refuses to start another fixed step once more than
+    /// `deadline` of real time elapsed since the frame began. The remaining time stays in the
+    /// accumulator for the next frames, bounding the worst-case frame time during catch-up.
+    ///
+    /// The frame start is only known when the clock is advanced with `advance_frame_at` or
+    /// `advance_frame_now`. Otherwise, this is the same as `step_fixed_update`.
+    pub fn step_fixed_update_with_deadline(&mut self, deadline: Duration) -> bool {
+        self.step_fixed_update_with_deadline_from(deadline, &SystemClock)
+    }
+
+    /// Like `step_fixed_update_with_deadline`, reading the current time from `source`.
+    pub fn step_fixed_update_with_deadline_from(
+        &mut self,
+        deadline: Duration,
+        source: &impl ClockSource,
+    ) -> bool {
+        match self.time_since_frame_start_from(source) {
+            Some(elapsed) if elapsed > deadline => {
+                self.in_fixed_step = false;
+                false
+            }
+            _ => self.step_fixed_update(),
+        }
+    }
+
+    /// Runs a fixed step like `step_fixed_update`, and returns a guard giving access to the
+    /// clock in the fixed update phase during that step:
+    /// `while let Some(time) = time.enter_fixed_update() { ... }`.
+    pub fn enter_fixed_update(&mut self) -> Option<FixedUpdateGuard<'_>> {
+        if self.step_fixed_update() {
+            Some(FixedUpdateGuard::new(self))
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the clock is in the fixed update phase, through a `FixedUpdateGuard`.
+    pub fn in_fixed_update(&self) -> bool {
+        self.fixed_phase
+    }
+
+    /// Checks if `step_fixed_update` would start a fixed step.
+    fn fixed_step_available(&self) -> bool {
+        let due = self.fixed_time_accumulator >= self.fixed_time
+            || (self.partial_step && self.fixed_time_accumulator > 0);
+        due && self.fixed_steps_this_frame < self.fixed_step_budget
+    }
+
+    /// Gets whether every due fixed step must run before the next frame.
+    pub fn require_drained_fixed_steps(&self) -> bool {
+        self.require_drained_fixed_steps
+    }
+
+    /// Sets whether every due fixed step must run before the next frame, which is checked
+    /// by `advance_frame` in strict mode. Defaults to false.
+    pub fn set_require_drained_fixed_steps(&mut self, require: bool) {
+        self.require_drained_fixed_steps = require;
+    }
+
+    /// Checks if a stepping policy replaces the catch-up policy.
+    pub fn has_stepping_policy(&self) -> bool {
+        self.stepping_policy.is_some()
+    }
+
+    /// Sets a stepping policy deciding how the accumulator turns into fixed steps, replacing
+    /// the catch-up policy. The clock owns the policy, which can keep state between frames.
+    /// Clones of this clock share it.
+    pub fn set_stepping_policy(&mut self, policy: impl SteppingPolicy + 'static) {
+        self.stepping_policy = Some(Policy(Arc::new(Mutex::new(policy))));
+        self.replan();
+    }
+
+    /// Removes the stepping policy, going back to the catch-up policy.
+    pub fn clear_stepping_policy(&mut self) {
+        self.stepping_policy = None;
+        self.replan();
+    }
+
+    /// Asks the stepping policy, or the catch-up policy, to plan the current frame.
+    #[inline]
+    fn step_plan(&self) -> StepPlan {
+        match &self.stepping_policy {
+            Some(policy) => policy.plan(&StepFrame {
+                delta_real_time: Duration::from_nanos(self.delta_real_time),
+                previous_delta_real_time: Duration::from_nanos(self.previous_delta_real_time),
+                fixed_time: Duration::from_nanos(self.fixed_time),
+                accumulator: Duration::from_nanos(self.fixed_time_accumulator),
+            }),
+            None => StepPlan {
+                max_steps: self.catch_up.max_steps(
+                    self.delta_real_time,
+                    self.previous_delta_real_time,
+                    self.fixed_time,
+                ),
+                ..StepPlan::default()
+            },
+        }
+    }
+
+    /// Plans the current frame again after a change of the stepping settings.
+    fn replan(&mut self) {
+        let plan = self.step_plan();
+        self.fixed_step_budget = plan.max_steps;
+        self.partial_step = plan.partial_step;
+    }
 }
 
 impl Default for Time {
     fn default() -> Time {
-        Time {
-            delta_time: Duration::from_secs(0),
-            delta_real_time: Duration::from_secs(0),
-            fixed_time: Duration::new(0, 16_666_666),
-            fixed_time_accumulator: Duration::new(0, 0),
-            frame_number: 0,
-            absolute_real_time: Duration::default(),
-            absolute_time: Duration::default(),
-            time_scale: 1.0,
-        }
+        Time::new(16_666_666, 1.0)
     }
 }
 
@@ -194,6 +2056,610 @@ mod tests {
         assert_eq!(time.time_scale(), 2.0);
         assert_eq!(time.fixed_time(), Duration::from_secs_f64(1.0 / 120.0));
     }
+
+    #[test]
+    fn min_delta_clamps_short_frames() {
+        let mut time = Time::default();
+        time.set_min_delta(Duration::from_millis(1));
+
+        time.advance_frame(Duration::from_secs(0));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(1));
+        assert_eq!(time.delta_time(), Duration::from_millis(1));
+        assert_eq!(time.absolute_real_time(), Duration::from_secs(0));
+
+        time.advance_frame(Duration::from_millis(5));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(5));
+        assert_eq!(time.absolute_time(), Duration::from_millis(5));
+    }
+
+    // Test that a hitch is spread over the following frames with smooth catch-up
+    #[test]
+    fn smooth_catch_up_bleeds_backlog() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_catch_up(CatchUp::Smooth { max_extra_steps: 1 });
+
+        let mut steps = vec![];
+        for delta in &[10, 100, 10, 10, 10] {
+            time.advance_frame(Duration::from_millis(*delta));
+            let mut count = 0;
+            while time.step_fixed_update() {
+                count += 1;
+            }
+            steps.push(count);
+        }
+        assert_eq!(steps, vec![1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn skip_render_when_behind() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_catch_up(CatchUp::Smooth { max_extra_steps: 0 });
+        time.set_render_skip_threshold(Some(Duration::from_millis(30)));
+
+        time.advance_frame(Duration::from_millis(10));
+        while time.step_fixed_update() {}
+        assert!(!time.should_skip_render());
+
+        time.advance_frame(Duration::from_millis(100));
+        while time.step_fixed_update() {}
+        assert!(time.should_skip_render());
+    }
+
+    #[test]
+    fn scale_layers_multiply() {
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.set_scale_layer("slowmo", 0.25);
+        time.set_scale_layer("debug", 4.0);
+        assert_eq!(time.time_scale(), 2.0);
+        assert_eq!(time.scale_layer("slowmo"), Some(0.25));
+
+        time.clear_scale_layer("debug");
+        assert_eq!(time.time_scale(), 0.5);
+        assert_eq!(time.base_time_scale(), 2.0);
+
+        time.advance_frame(Duration::from_secs(1));
+        assert_eq!(time.delta_time(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn advance_to_absolute_time() {
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_to(Duration::from_millis(250));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(250));
+        time.advance_to(Duration::from_millis(400));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(150));
+        assert_eq!(time.absolute_real_time(), Duration::from_millis(400));
+        assert_eq!(time.absolute_time(), Duration::from_millis(800));
+
+        time.advance_to(Duration::from_millis(100));
+        assert_eq!(time.delta_real_time(), Duration::from_secs(0));
+        assert_eq!(time.frame_number(), 3);
+    }
+
+    #[test]
+    fn scaled_real_conversions() {
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        assert_eq!(
+            time.to_scaled(Duration::from_secs(2)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(time.to_real(Duration::from_secs(1)), Duration::from_secs(2));
+
+        time.set_time_scale(0.0);
+        assert_eq!(
+            time.to_scaled(Duration::from_secs(2)),
+            Duration::from_secs(0)
+        );
+        assert_eq!(time.to_real(Duration::from_secs(1)), Duration::MAX);
+        assert_eq!(time.to_real(Duration::from_secs(0)), Duration::from_secs(0));
+    }
+
+    // Test that absolute time never drifts away from the sum of the scaled deltas
+    #[test]
+    fn absolute_time_is_sum_of_deltas() {
+        let mut time = Time::default();
+        time.set_time_scale(0.3);
+        let mut sum = Duration::from_secs(0);
+        for _ in 0..100_000 {
+            time.advance_frame(Duration::from_secs_f64(1.0 / 144.0));
+            sum += time.delta_time();
+        }
+        assert_eq!(time.absolute_time(), sum);
+    }
+
+    // Test that fractions of nanoseconds lost to scaling are carried between frames
+    #[test]
+    fn scaled_remainder_is_carried() {
+        let mut time = Time::default();
+        time.set_time_scale_f64(0.25);
+        for _ in 0..4_000 {
+            time.advance_frame(Duration::from_nanos(1));
+        }
+        assert_eq!(time.absolute_time(), Duration::from_nanos(1_000));
+        assert_eq!(time.time_scale_f64(), 0.25);
+    }
+
+    #[test]
+    fn secondary_fixed_rates() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_fixed_rate("ai", Duration::from_millis(100));
+        time.advance_frame(Duration::from_millis(250));
+        let mut ai_steps = 0;
+        while time.step_fixed_rate("ai") {
+            ai_steps += 1;
+        }
+        assert_eq!(ai_steps, 2);
+        assert_eq!(time.fixed_rate_alpha("ai"), Some(0.5));
+        assert_eq!(
+            time.last_fixed_rate_step_time("ai"),
+            Some(Duration::from_millis(200))
+        );
+        // The main fixed step is independent.
+        assert!(time.step_fixed_update());
+        assert!(!time.step_fixed_rate("physics"));
+        assert_eq!(time.fixed_rate_alpha("physics"), None);
+    }
+
+    #[test]
+    fn rounding_policies() {
+        let run = |rounding| {
+            let mut time = Time::default();
+            time.set_time_scale_ratio(1, 3);
+            time.set_rounding(rounding);
+            for _ in 0..300 {
+                time.advance_frame(Duration::from_nanos(2));
+            }
+            time.absolute_time()
+        };
+        assert_eq!(run(DeltaRounding::Truncate), Duration::from_nanos(0));
+        assert_eq!(run(DeltaRounding::Nearest), Duration::from_nanos(300));
+        assert_eq!(run(DeltaRounding::Carry), Duration::from_nanos(200));
+    }
+
+    #[test]
+    fn exact_ratio_scale() {
+        let mut time = Time::default();
+        time.set_time_scale_ratio(1, 3);
+        assert_eq!(time.time_scale_ratio(), (1, 3));
+        for _ in 0..3_000 {
+            time.advance_frame(Duration::from_nanos(1));
+        }
+        assert_eq!(time.absolute_time(), Duration::from_nanos(1_000));
+
+        time.set_scale_layer("slowmo", 0.5);
+        assert_eq!(time.time_scale_ratio(), (1, 6));
+    }
+
+    #[test]
+    fn const_constructor() {
+        const CLOCK: Time = Time::new(10_000_000, 0.5);
+        let mut time = CLOCK;
+        assert_eq!(time.fixed_time(), Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(20));
+        assert_eq!(time.delta_time(), Duration::from_millis(10));
+        assert_eq!(Time::new(16_666_666, 1.0), Time::default());
+    }
+
+    #[test]
+    fn debug_snapshot_values() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale(0.5);
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {}
+
+        let snapshot = time.debug_snapshot();
+        assert!(approx_zero(snapshot.fps - 40.0));
+        assert_eq!(snapshot.delta_time, Duration::from_micros(12_500));
+        assert_eq!(snapshot.fixed_steps, 2);
+        assert!(approx_zero(snapshot.accumulator_fill - 0.5));
+        assert!(!snapshot.hitch);
+
+        time.advance_frame(Duration::from_millis(150));
+        assert!(time.debug_snapshot().hitch);
+    }
+
+    #[test]
+    fn slow_frame_hook() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_slow_frame_hook(Duration::from_millis(40), move |frame: &SlowFrame| {
+            sender.send((frame.frame_number, frame.steps_due)).unwrap();
+        });
+        time.advance_frame(Duration::from_millis(20));
+        while time.step_fixed_update() {}
+        assert!(receiver.try_recv().is_err());
+        time.advance_frame(Duration::from_millis(50));
+        assert_eq!(receiver.try_recv(), Ok((2, 5)));
+    }
+
+    #[test]
+    fn suspend_detection_policies() {
+        let gap = Duration::from_secs(3_600);
+        let resumed = [ClockEvent::Resumed { gap }];
+        let mut time = Time::default();
+        time.set_suspend_detection(
+            Duration::from_secs(5),
+            SuspendPolicy::Clamp(Duration::from_secs(1)),
+        );
+        time.advance_frame(gap);
+        assert_eq!(time.events(), &resumed);
+        assert_eq!(time.delta_real_time(), Duration::from_secs(1));
+
+        time.set_suspend_detection(Duration::from_secs(5), SuspendPolicy::Discard);
+        time.advance_frame(gap);
+        assert_eq!(time.delta_real_time(), Duration::from_secs(0));
+
+        time.set_suspend_detection(Duration::from_secs(5), SuspendPolicy::Keep);
+        time.advance_frame(gap);
+        assert_eq!(time.delta_real_time(), gap);
+
+        time.advance_frame(Duration::from_millis(16));
+        assert!(time.events().is_empty());
+    }
+
+    #[test]
+    fn freeze_when_unfocused() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_freeze_when_unfocused(true);
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(10));
+        while time.step_fixed_update() {}
+
+        time.set_focused(false);
+        assert_eq!(time.time_scale(), 0.0);
+        time.advance_frame(Duration::from_millis(100));
+        assert!(!time.step_fixed_update());
+        assert_eq!(time.delta_time(), Duration::from_secs(0));
+        assert_eq!(time.absolute_real_time(), Duration::from_millis(110));
+
+        time.set_focused(true);
+        assert_eq!(time.time_scale(), 2.0);
+        time.advance_frame(Duration::from_millis(10));
+        assert_eq!(time.absolute_time(), Duration::from_millis(40));
+        assert!(time.step_fixed_update());
+        assert!(!time.step_fixed_update());
+    }
+
+    #[test]
+    fn excluded_sections() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(10));
+        while time.step_fixed_update() {}
+
+        // A blocking load between two frames.
+        time.begin_excluded_section();
+        time.end_excluded_section();
+        time.advance_frame(Duration::from_secs(3));
+        assert!(time.is_frame_excluded());
+        assert_eq!(time.delta_time(), Duration::from_secs(0));
+        assert!(!time.step_fixed_update());
+
+        // A loading screen rendered over several frames.
+        time.begin_excluded_section();
+        time.advance_frame(Duration::from_millis(500));
+        time.advance_frame(Duration::from_millis(500));
+        time.end_excluded_section();
+        time.advance_frame(Duration::from_millis(10));
+        assert!(time.is_frame_excluded());
+        time.advance_frame(Duration::from_millis(10));
+        assert!(!time.is_frame_excluded());
+        assert!(time.step_fixed_update());
+
+        assert_eq!(time.excluded_time(), Duration::from_millis(4_010));
+        assert_eq!(time.absolute_time(), Duration::from_millis(20));
+        assert_eq!(time.absolute_real_time(), Duration::from_millis(4_030));
+    }
+
+    #[test]
+    fn frame_history_queries() {
+        let mut time = Time::default();
+        time.set_frame_history_len(3);
+        for _ in 0..5 {
+            time.advance_frame(Duration::from_millis(10));
+        }
+        assert_eq!(time.absolute_time_of_frame(2), None);
+        assert_eq!(
+            time.absolute_time_of_frame(3),
+            Some(Duration::from_millis(30))
+        );
+        assert_eq!(
+            time.absolute_real_time_of_frame(5),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(time.absolute_time_of_frame(6), None);
+        assert_eq!(
+            time.frames_between(Duration::from_millis(35), Duration::from_millis(50)),
+            4..5
+        );
+        assert_eq!(
+            time.frames_between(Duration::from_millis(0), Duration::from_secs(1)),
+            3..6
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "strict")]
+    #[should_panic(expected = "delta_time read inside a fixed step")]
+    fn strict_mode_catches_delta_in_fixed_step() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {
+            time.delta_time();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "strict")]
+    fn strict_mode_allows_snapshot_in_fixed_step() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {
+            assert_eq!(time.debug_snapshot().delta_time, Duration::from_millis(25));
+        }
+    }
+
+    #[test]
+    fn fixed_rate_change() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(35));
+        time.step_fixed_update();
+
+        let mut proportional = time.clone();
+        let change = proportional
+            .change_fixed_time(Duration::from_millis(20), RateChangePolicy::Proportional);
+        assert_eq!(change.first_tick, 2);
+        assert_eq!((change.steps_due_before, change.steps_due_after), (2, 2));
+        assert_eq!(proportional.fixed_step_alpha(), 1.0);
+
+        let mut kept = time.clone();
+        let change = kept.change_fixed_time(Duration::from_millis(20), RateChangePolicy::Keep);
+        assert_eq!(change.steps_due_after, 1);
+        assert_eq!(change.discarded, Duration::from_secs(0));
+
+        let change = time.change_fixed_time(Duration::from_millis(5), RateChangePolicy::Flush);
+        assert_eq!(change.discarded, Duration::from_millis(25));
+        assert!(!time.step_fixed_update());
+    }
+
+    #[test]
+    fn capture_mode() {
+        let mut time = Time::default();
+        time.set_capture_fps(Some(60));
+        for _ in 0..59 {
+            time.advance_frame(Duration::from_millis(100));
+        }
+        assert_eq!(time.delta_time(), Duration::from_nanos(16_666_667));
+        time.advance_frame(Duration::from_secs(0));
+        assert_eq!(time.absolute_real_time(), Duration::from_secs(1));
+        time.set_capture_fps(None);
+        time.advance_frame(Duration::from_millis(100));
+        assert_eq!(time.delta_time(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn input_timestamps() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale(0.5);
+        assert_eq!(time.real_time_at(start), None);
+        time.advance_frame_at(ms(0));
+        time.advance_frame_at(ms(40));
+        while time.step_fixed_update() {}
+        time.advance_frame_at(ms(65));
+
+        assert_eq!(time.real_time_at(ms(50)), Some(Duration::from_millis(50)));
+        assert_eq!(time.real_time_at(ms(70)), Some(Duration::from_millis(70)));
+        assert_eq!(time.game_time_at(ms(50)), Some(Duration::from_millis(25)));
+        // Ticks 1 to 4 ran, ticks 5 and 6 are due this frame.
+        assert_eq!(time.fixed_tick_at(ms(39)), Some(4));
+        assert_eq!(time.fixed_tick_at(ms(40)), Some(5));
+        assert_eq!(time.fixed_tick_at(ms(59)), Some(6));
+        time.set_fixed_time(Duration::from_secs(0));
+        assert_eq!(time.fixed_tick_at(ms(59)), None);
+    }
+
+    #[test]
+    fn fixed_ticks_in_interval() {
+        let time = Time::new(10_000_000, 1.0);
+        let ticks = |start, end| {
+            time.fixed_ticks_between(Duration::from_millis(start), Duration::from_millis(end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ticks(0, 35), vec![1, 2, 3]);
+        assert_eq!(ticks(10, 30), vec![1, 2]);
+        assert_eq!(ticks(11, 41), vec![2, 3, 4]);
+        assert_eq!(ticks(15, 19), vec![]);
+        assert_eq!(ticks(40, 20), vec![]);
+        assert_eq!(
+            Time::new(0, 1.0).fixed_ticks_between(Duration::from_secs(0), Duration::MAX),
+            0..0
+        );
+    }
+
+    #[test]
+    fn fixed_step_counter() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        for _ in 0..3 {
+            time.advance_frame(Duration::from_millis(25));
+            while time.step_fixed_update() {}
+        }
+        assert_eq!(time.fixed_step_count(), 7);
+        assert_eq!(time.last_fixed_step_time(), Duration::from_millis(70));
+
+        time.set_fixed_time(Duration::from_millis(5));
+        time.advance_frame(Duration::from_millis(5));
+        while time.step_fixed_update() {}
+        assert_eq!(time.fixed_step_count(), 9);
+        assert_eq!(time.last_fixed_step_time(), Duration::from_millis(80));
+    }
+
+    #[test]
+    fn simulate_fast_forward() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale(2.0);
+        let mut last_step = Duration::from_secs(0);
+        let steps = time.simulate(Duration::from_secs(600), |time| {
+            last_step = time.absolute_time();
+        });
+        assert_eq!(steps, 60_000);
+        assert_eq!(time.fixed_step_count(), 60_000);
+        assert_eq!(last_step, Duration::from_secs(1_200));
+        assert_eq!(time.absolute_real_time(), Duration::from_secs(600));
+    }
+
+    #[test]
+    fn tick_fingerprint_detects_desync() {
+        let mut a = Time::default();
+        let mut b = Time::default();
+        for time in [&mut a, &mut b].iter_mut() {
+            time.set_time_scale_ratio(1, 2);
+            time.advance_frame(Duration::from_millis(40));
+            while time.step_fixed_update() {}
+        }
+        assert_eq!(a.tick_fingerprint(), b.tick_fingerprint());
+        assert_eq!(fnv1a(&[]), 0xcbf2_9ce4_8422_2325);
+
+        b.set_time_scale_ratio(2, 4);
+        assert_eq!(a.tick_fingerprint(), b.tick_fingerprint());
+        b.set_time_scale_ratio(1, 3);
+        assert_ne!(a.tick_fingerprint(), b.tick_fingerprint());
+    }
+
+    #[test]
+    fn interpolated_render_time() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {}
+        assert!(approx_zero(time.fixed_step_alpha() - 0.5));
+        assert_eq!(time.interpolated_time(), Duration::from_millis(25));
+
+        time.set_catch_up(CatchUp::Smooth { max_extra_steps: 0 });
+        time.advance_frame(Duration::from_millis(100));
+        time.step_fixed_update();
+        assert_eq!(time.fixed_step_alpha(), 1.0);
+        assert_eq!(time.interpolated_time(), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn non_monotonic_instants() {
+        let start = Instant::now();
+        let mut time = Time::default();
+        time.set_min_delta(Duration::from_micros(100));
+        time.advance_frame_at(start + Duration::from_millis(10));
+        time.advance_frame_at(start + Duration::from_millis(26));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(16));
+        assert!(time.events().is_empty());
+
+        time.advance_frame_at(start + Duration::from_millis(20));
+        assert_eq!(time.delta_real_time(), Duration::from_micros(100));
+        assert_eq!(
+            time.events(),
+            &[ClockEvent::NonMonotonic {
+                backwards: Duration::from_millis(6)
+            }]
+        );
+        assert_eq!(time.frame_start(), Some(start + Duration::from_millis(26)));
+        time.advance_frame_at(start + Duration::from_millis(36));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn halt_detection_repeats_previous_delta() {
+        let mut time = Time::default();
+        time.set_halt_threshold(Some(Duration::from_secs(1)));
+        time.set_suspend_detection(Duration::from_secs(60), SuspendPolicy::Discard);
+        time.advance_frame(Duration::from_millis(16));
+        time.advance_frame(Duration::from_secs(20));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(16));
+        assert_eq!(
+            time.events(),
+            &[ClockEvent::Halted {
+                gap: Duration::from_secs(20)
+            }]
+        );
+
+        time.advance_frame(Duration::from_secs(120));
+        assert_eq!(time.delta_real_time(), Duration::from_secs(0));
+        assert_eq!(time.events().len(), 1);
+    }
+
+    #[test]
+    fn frame_start_instant() {
+        let mut time = Time::default();
+        assert_eq!(time.time_since_frame_start(), None);
+        time.advance_frame_now();
+        let start = time.frame_start().unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(time.time_since_frame_start().unwrap() >= Duration::from_millis(2));
+        time.advance_frame_now();
+        assert!(time.frame_start().unwrap() - start >= Duration::from_millis(2));
+        assert!(time.delta_real_time() >= Duration::from_millis(2));
+    }
+
+    #[test]
+    fn fixed_step_deadline() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(1));
+        time.advance_frame(Duration::from_millis(10));
+        assert!(time.step_fixed_update_with_deadline(Duration::from_secs(0)));
+
+        let clock = ManualClock::new(Instant::now());
+        time.advance_frame_at(clock.now());
+        clock.advance(Duration::from_millis(20));
+        time.advance_frame_at(clock.now());
+        // Each step takes 2ms: the third one starts 4ms into the frame, the fourth would
+        // start past the 5ms deadline.
+        let mut steps = 0;
+        while time.step_fixed_update_with_deadline_from(Duration::from_millis(5), &clock) {
+            steps += 1;
+            clock.advance(Duration::from_millis(2));
+        }
+        assert_eq!(steps, 3);
+        assert_eq!(time.fixed_steps_this_frame(), 3);
+        assert!(time.step_fixed_update());
+    }
+
+    #[test]
+    fn speed_presets_ramp() {
+        let mut time = Time::default();
+        assert_eq!(time.speed_preset(), Some("1x"));
+        assert!(!time.set_speed_preset("8x"));
+        assert!(time.set_speed_preset("4x"));
+        assert_eq!(time.speed_preset(), Some("4x"));
+        assert_eq!(time.time_scale(), 1.0);
+
+        time.advance_frame(Duration::from_millis(125));
+        assert_eq!(time.time_scale(), 2.5);
+        time.advance_frame(Duration::from_millis(125));
+        assert_eq!(time.time_scale(), 4.0);
+        assert_eq!(time.time_scale_target(), None);
+
+        time.set_speed_preset("0.5x");
+        time.set_time_scale(3.0);
+        assert_eq!(time.speed_preset(), None);
+        time.advance_frame(Duration::from_millis(500));
+        assert_eq!(time.time_scale(), 3.0);
+    }
+
+    #[allow(clippy::manual_range_contains)]
     fn approx_zero(v: f64) -> bool {
         v >= -0.000001 && v <= 0.000001
     }
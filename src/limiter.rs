@@ -0,0 +1,626 @@
+//! Caps the frame rate of a game loop by waiting out the rest of each frame.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{ClockSource, SystemClock};
+
+/// How a `FrameLimiter` waits for the end of the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimiterStrategy {
+    /// Sleeps for the whole remaining time. Cheap, but the OS may oversleep.
+    Sleep,
+    /// Busy-waits for the whole remaining time. Precise, but keeps a core busy.
+    Spin,
+    /// Sleeps until `spin_for` before the deadline, then busy-waits for the rest.
+    SleepAndSpin {
+        /// Time before the deadline at which sleeping stops.
+        spin_for: Duration,
+    },
+    /// Like `SleepAndSpin`, but on Windows the timer resolution is raised to 1ms while
+    /// sleeping. The default Windows resolution makes `thread::sleep` overshoot by up to
+    /// 15ms, which ruins frame pacing. On other platforms this is the same as `SleepAndSpin`.
+    HighResolutionSleep {
+        /// Time before the deadline at which sleeping stops.
+        spin_for: Duration,
+    },
+}
+
+impl Default for LimiterStrategy {
+    fn default() -> Self {
+        LimiterStrategy::SleepAndSpin {
+            spin_for: Duration::from_millis(1),
+        }
+    }
+}
+
+/// Waits at the end of each frame so that frames last at least a target duration.
+///
+/// Call `wait` once per frame, and pass the returned duration to `Time::advance_frame`.
+#[derive(Clone, Debug)]
+pub struct FrameLimiter<S: ClockSource = SystemClock> {
+    source: S,
+    /// Minimum duration of a frame.
+    frame_duration: Duration,
+    strategy: LimiterStrategy,
+    /// Minimum duration of a frame while the app is unfocused.
+    background_frame_duration: Option<Duration>,
+    /// False while the app window lost focus.
+    focused: bool,
+    /// True in idle mode, for menus and paused states.
+    idle: bool,
+    /// Minimum duration of a frame in idle mode.
+    idle_frame_duration: Duration,
+    /// Latest end of the current frame, set by `wake_before`.
+    wake_at: Option<Instant>,
+    /// When the previous call to `wait` returned.
+    last_frame: Instant,
+    /// Refresh interval of the display and divisor of its refresh rate, when pacing to
+    /// vertical blanks.
+    vsync: Option<(Duration, u32)>,
+    /// Shortest and longest frame durations, when pacing within a frame time corridor.
+    corridor: Option<(Duration, Duration)>,
+    /// Smoothed time spent working on a frame before calling `wait`.
+    work_estimate: Duration,
+    /// When the current frame is planned to be presented.
+    next_present: Instant,
+    /// Vertical blanks missed by the last frame.
+    missed_vsyncs: u32,
+}
+
+impl FrameLimiter {
+    /// Creates a limiter capping the game to `fps` frames per second.
+    ///
+    /// ## Panics
+    /// This will panic if fps is 0.
+    pub fn new(fps: u32) -> Self {
+        assert!(fps > 0);
+        FrameLimiter::from_frame_duration(Duration::from_secs(1) / fps)
+    }
+
+    /// Creates a limiter making every frame last at least `frame_duration`.
+    pub fn from_frame_duration(frame_duration: Duration) -> Self {
+        FrameLimiter::with_source(SystemClock, frame_duration)
+    }
+}
+
+impl<S: ClockSource> FrameLimiter<S> {
+    /// Creates a limiter making every frame last at least `frame_duration`, reading the time
+    /// from `source` and waiting on it.
+    pub fn with_source(source: S, frame_duration: Duration) -> Self {
+        let now = source.now();
+        FrameLimiter {
+            source,
+            frame_duration,
+            strategy: LimiterStrategy::default(),
+            background_frame_duration: None,
+            focused: true,
+            idle: false,
+            idle_frame_duration: Duration::from_millis(100),
+            wake_at: None,
+            last_frame: now,
+            vsync: None,
+            corridor: None,
+            work_estimate: Duration::from_secs(0),
+            next_present: now + frame_duration,
+            missed_vsyncs: 0,
+        }
+    }
+
+    /// Gets the minimum duration of a frame.
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    /// Sets the minimum duration of a frame.
+    pub fn set_frame_duration(&mut self, frame_duration: Duration) {
+        self.frame_duration = frame_duration;
+    }
+
+    /// Gets the minimum duration of a frame while the app is unfocused.
+    pub fn background_frame_duration(&self) -> Option<Duration> {
+        self.background_frame_duration
+    }
+
+    /// Sets the minimum duration of a frame while the app is unfocused, for example 100ms to
+    /// run at 10 frames per second while minimized. `None` keeps the regular frame duration.
+    pub fn set_background_frame_duration(&mut self, frame_duration: Option<Duration>) {
+        self.background_frame_duration = frame_duration;
+    }
+
+    /// Checks if the app window has focus, as last reported with `set_focused`.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Reports whether the app window has focus. While unfocused, frames last at least the
+    /// background frame duration. See also `Time::set_focused`.
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Checks if the limiter is in idle mode.
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Switches idle mode on or off, for example with
+    /// `limiter.set_idle(time.time_scale() == 0.0)`
+    /// in menus and paused states. Frames then last at least the idle frame duration, spent
+    /// sleeping instead of spinning, to save power. Vertical blank and frame time corridor
+    /// pacing resume when idle mode is turned off.
+    pub fn set_idle(&mut self, idle: bool) {
+        self.idle = idle;
+    }
+
+    /// Gets the minimum duration of a frame in idle mode.
+    pub fn idle_frame_duration(&self) -> Duration {
+        self.idle_frame_duration
+    }
+
+    /// Sets the minimum duration of a frame in idle mode. Defaults to 100ms, 10 frames per
+    /// second.
+    pub fn set_idle_frame_duration(&mut self, frame_duration: Duration) {
+        self.idle_frame_duration = frame_duration;
+    }
+
+    /// Ends the current frame no later than `delay` from now, for example the time until the
+    /// next real time alarm (see `AlarmClock::time_until_next`), so that long idle frames
+    /// don't make it fire late.
+    pub fn wake_before(&mut self, delay: Duration) {
+        let at = self.source.now() + delay;
+        self.wake_at = Some(self.wake_at.map_or(at, |wake_at| wake_at.min(at)));
+    }
+
+    /// Gets the minimum duration of the current frame, depending on idle mode and focus.
+    pub fn current_frame_duration(&self) -> Duration {
+        if self.idle {
+            return self.idle_frame_duration;
+        }
+        match self.background_frame_duration {
+            Some(background) if !self.focused => background,
+            _ => self.frame_duration,
+        }
+    }
+
+    /// Paces frames to the vertical blanks of the display instead of an arbitrary frame rate,
+    /// presenting every `divisor` refreshes: 1 for the full refresh rate, 2 for half, 3 for a
+    /// third. This avoids the judder of a frame rate beating against the refresh interval.
+    ///
+    /// With several displays, pass the refresh rate of each display the window is on: the
+    /// slowest one is used. `None` for either goes back to the frame duration.
+    /// This turns off frame time corridor pacing.
+    ///
+    /// ## Panics
+    /// This will panic if divisor is 0, or if a refresh rate is not strictly positive.
+    pub fn set_vsync(&mut self, refresh_rates: Option<&[f64]>, divisor: u32) {
+        assert!(divisor > 0);
+        self.vsync = refresh_rates
+            .and_then(|rates| rates.iter().copied().reduce(f64::min))
+            .map(|rate| {
+                assert!(rate > 0.0 && rate.is_finite());
+                (Duration::from_secs_f64(1.0 / rate), divisor)
+            });
+        if let Some(period) = self.vsync_period() {
+            self.corridor = None;
+            self.next_present = self.last_frame + period;
+        }
+    }
+
+    /// Gets the duration of a frame when pacing to vertical blanks: the refresh interval
+    /// times the divisor. The frame duration is kept for when vertical blank pacing stops.
+    pub fn vsync_period(&self) -> Option<Duration> {
+        self.vsync.map(|(interval, divisor)| interval * divisor)
+    }
+
+    /// Paces frames for variable refresh rate (G-Sync, FreeSync) displays: instead of a fixed
+    /// rate, each frame lasts the smoothed time the game needs for a frame, kept between
+    /// `min` and `max`. Pass the range of refresh intervals the display supports.
+    /// Frames then last a steady duration that follows the load. `None` goes back to the
+    /// frame duration. This turns off vertical blank pacing.
+    ///
+    /// ## Panics
+    /// This will panic if min is longer than max.
+    pub fn set_frame_time_corridor(&mut self, corridor: Option<(Duration, Duration)>) {
+        if let Some((min, max)) = corridor {
+            assert!(min <= max);
+            self.vsync = None;
+            self.work_estimate = min;
+            self.next_present = self.last_frame + min;
+        }
+        self.corridor = corridor;
+    }
+
+    /// Gets the shortest and longest frame durations, when pacing within a frame time
+    /// corridor.
+    pub fn frame_time_corridor(&self) -> Option<(Duration, Duration)> {
+        self.corridor
+    }
+
+    /// Gets when `wait` plans to end the current frame, before any late frame correction.
+    /// Simulate up to this point in time to align simulation with presentation.
+    pub fn present_deadline(&self) -> Instant {
+        self.next_present
+    }
+
+    /// Gets the refresh interval of the display frames are paced to, if any.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.vsync.map(|(interval, _)| interval)
+    }
+
+    /// Gets how many vertical blanks the last frame missed because it was late,
+    /// when pacing to vertical blanks. Zero when the frame was on time.
+    pub fn missed_vsyncs(&self) -> u32 {
+        self.missed_vsyncs
+    }
+
+    /// Gets how the limiter waits for the end of the frame.
+    pub fn strategy(&self) -> LimiterStrategy {
+        self.strategy
+    }
+
+    /// Sets how the limiter waits for the end of the frame.
+    pub fn set_strategy(&mut self, strategy: LimiterStrategy) {
+        self.strategy = strategy;
+    }
+
+    /// Waits until the frame lasted at least the frame duration, then returns the real time
+    /// elapsed since the previous call.
+    ///
+    /// When pacing to vertical blanks, frames are presented at exact multiples of the refresh
+    /// interval, and a late frame waits for the next vertical blank instead of shifting the
+    /// schedule. See `missed_vsyncs`.
+    pub fn wait(&mut self) -> Duration {
+        let start = self.source.now();
+        // Paced to the display, rather than to a frame duration.
+        let paced = self.focused && !self.idle;
+        let deadline = match (self.vsync, self.corridor) {
+            (Some((interval, divisor)), _) if paced => {
+                let (deadline, missed) = next_vsync(self.next_present, start, interval);
+                self.missed_vsyncs = missed;
+                self.next_present = deadline + interval * divisor;
+                deadline
+            }
+            (_, Some(_)) if paced => {
+                // Exponential moving average, to follow the load without reacting to one
+                // frame.
+                let work = start - self.last_frame;
+                self.work_estimate = (self.work_estimate * 7 + work) / 8;
+                self.next_present
+            }
+            _ => self.last_frame + self.current_frame_duration(),
+        };
+        let deadline = match self.wake_at.take() {
+            Some(wake_at) => deadline.min(wake_at),
+            None => deadline,
+        };
+        let strategy = if self.idle {
+            LimiterStrategy::Sleep
+        } else {
+            self.strategy
+        };
+        self.source.wait_until(deadline, strategy);
+        let now = self.source.now();
+        match self.corridor {
+            Some((min, max)) if self.vsync.is_none() && paced => {
+                self.next_present = now + self.work_estimate.max(min).min(max);
+            }
+            _ if self.vsync.is_none() || !paced => {
+                self.next_present = now + self.current_frame_duration();
+            }
+            _ => {}
+        }
+        let elapsed = now - self.last_frame;
+        self.last_frame = now;
+        elapsed
+    }
+}
+
+/// Wakes an `InterruptibleLimiter` before its next tick, from any thread.
+#[derive(Clone, Debug, Default)]
+pub struct WakeHandle {
+    /// Whether a wake is pending, and the condition variable the limiter sleeps on.
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl WakeHandle {
+    /// Wakes the limiter, or makes its next wait return immediately if it isn't waiting.
+    pub fn wake(&self) {
+        let (pending, condvar) = &*self.inner;
+        *pending.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        condvar.notify_all();
+    }
+}
+
+/// What ended a wait of an `InterruptibleLimiter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ServerWait {
+    /// Real time elapsed since the previous wait returned.
+    pub elapsed: Duration,
+    /// True if a `WakeHandle` ended the wait before the tick was due.
+    pub early: bool,
+}
+
+/// Paces the ticks of a headless server by sleeping until the next tick, while letting
+/// event-driven I/O (a network packet arrived) wake it early through a `WakeHandle`.
+///
+/// Early wakes don't shift the tick schedule: the next wait still ends at the same tick.
+#[derive(Debug)]
+pub struct InterruptibleLimiter {
+    /// Duration of a tick.
+    tick_duration: Duration,
+    /// When the next tick is due.
+    next_tick: Instant,
+    /// When the previous call to `wait` returned.
+    last_wake: Instant,
+    handle: WakeHandle,
+}
+
+impl InterruptibleLimiter {
+    /// Creates a limiter running `ticks_per_second` ticks per second.
+    ///
+    /// ## Panics
+    /// This will panic if ticks_per_second is 0.
+    pub fn new(ticks_per_second: u32) -> Self {
+        assert!(ticks_per_second > 0);
+        let tick_duration = Duration::from_secs(1) / ticks_per_second;
+        let now = Instant::now();
+        InterruptibleLimiter {
+            tick_duration,
+            next_tick: now + tick_duration,
+            last_wake: now,
+            handle: WakeHandle::default(),
+        }
+    }
+
+    /// Gets the duration of a tick.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// Gets a handle that can wake the limiter from other threads.
+    pub fn handle(&self) -> WakeHandle {
+        self.handle.clone()
+    }
+
+    /// Sleeps until the next tick is due or the limiter is woken, whichever comes first.
+    ///
+    /// When a tick is missed by more than a whole tick, the schedule restarts from now
+    /// instead of running the missed ticks back to back.
+    pub fn wait(&mut self) -> ServerWait {
+        let (pending, condvar) = &*self.handle.inner;
+        let mut woken = pending.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if *woken {
+                break;
+            }
+            let now = Instant::now();
+            if now >= self.next_tick {
+                break;
+            }
+            woken = condvar
+                .wait_timeout(woken, self.next_tick - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        let early = std::mem::replace(&mut *woken, false);
+        drop(woken);
+
+        let now = Instant::now();
+        if !early || now >= self.next_tick {
+            self.next_tick += self.tick_duration;
+            if self.next_tick < now {
+                self.next_tick = now + self.tick_duration;
+            }
+        }
+        let elapsed = now - self.last_wake;
+        self.last_wake = now;
+        ServerWait { elapsed, early }
+    }
+}
+
+/// Gets the first vertical blank at or after `present` that is not in the past, and the number
+/// of vertical blanks skipped to reach it.
+fn next_vsync(present: Instant, now: Instant, interval: Duration) -> (Instant, u32) {
+    if now <= present {
+        return (present, 0);
+    }
+    let late = (now - present).as_nanos();
+    let missed = (late / interval.as_nanos().max(1) + 1) as u32;
+    (present + interval * missed, missed)
+}
+
+/// Waits until `deadline` using the given strategy.
+pub(crate) fn wait_until(deadline: Instant, strategy: LimiterStrategy) {
+    match strategy {
+        LimiterStrategy::Sleep => sleep_until(deadline),
+        LimiterStrategy::Spin => spin_until(deadline),
+        LimiterStrategy::SleepAndSpin { spin_for } => {
+            sleep_until(deadline.checked_sub(spin_for).unwrap_or(deadline));
+            spin_until(deadline);
+        }
+        LimiterStrategy::HighResolutionSleep { spin_for } => {
+            let sleep_deadline = deadline.checked_sub(spin_for).unwrap_or(deadline);
+            if Instant::now() < sleep_deadline {
+                let _resolution = TimerResolution::request();
+                sleep_until(sleep_deadline);
+            }
+            spin_until(deadline);
+        }
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if now < deadline {
+        thread::sleep(deadline - now);
+    }
+}
+
+fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+/// Raises the resolution of the OS timer to 1ms while alive.
+struct TimerResolution;
+
+impl TimerResolution {
+    fn request() -> Self {
+        platform::begin_period();
+        TimerResolution
+    }
+}
+
+impl Drop for TimerResolution {
+    fn drop(&mut self) {
+        platform::end_period();
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    #[link(name = "winmm")]
+    extern "system" {
+        fn timeBeginPeriod(period: u32) -> u32;
+        fn timeEndPeriod(period: u32) -> u32;
+    }
+
+    pub(super) fn begin_period() {
+        unsafe {
+            timeBeginPeriod(1);
+        }
+    }
+
+    pub(super) fn end_period() {
+        unsafe {
+            timeEndPeriod(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub(super) fn begin_period() {}
+
+    pub(super) fn end_period() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::{Duration, Instant};
+
+    fn manual_limiter(frame_duration: Duration) -> (ManualClock, FrameLimiter<ManualClock>) {
+        let clock = ManualClock::new(Instant::now());
+        let limiter = FrameLimiter::with_source(clock.clone(), frame_duration);
+        (clock, limiter)
+    }
+
+    #[test]
+    fn frames_last_at_least_frame_duration() {
+        for strategy in &[
+            LimiterStrategy::Sleep,
+            LimiterStrategy::Spin,
+            LimiterStrategy::default(),
+            LimiterStrategy::HighResolutionSleep {
+                spin_for: Duration::from_millis(1),
+            },
+        ] {
+            let deadline = Instant::now() + Duration::from_millis(2);
+            super::wait_until(deadline, *strategy);
+            assert!(Instant::now() >= deadline);
+        }
+
+        let (clock, mut limiter) = manual_limiter(Duration::from_millis(5));
+        assert_eq!(limiter.wait(), Duration::from_millis(5));
+        // Frames shorter than the frame duration are padded, longer ones are not.
+        clock.advance(Duration::from_millis(3));
+        assert_eq!(limiter.wait(), Duration::from_millis(5));
+        clock.advance(Duration::from_millis(8));
+        assert_eq!(limiter.wait(), Duration::from_millis(8));
+    }
+
+    #[test]
+    fn interruptible_wakes_early() {
+        let start = Instant::now();
+        let mut limiter = InterruptibleLimiter::new(20);
+        let handle = limiter.handle();
+        std::thread::spawn(move || handle.wake()).join().unwrap();
+        assert!(limiter.wait().early);
+
+        // The schedule is kept: the next wait ends at the first tick.
+        assert!(!limiter.wait().early);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn vsync_pacing() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(5);
+        let next = |now: u64| {
+            let (deadline, missed) =
+                super::next_vsync(start, start + Duration::from_millis(now), interval);
+            (deadline - start, missed)
+        };
+        assert_eq!(next(0), (Duration::from_millis(0), 0));
+        assert_eq!(next(3), (Duration::from_millis(5), 1));
+        assert_eq!(next(12), (Duration::from_millis(15), 3));
+
+        let (clock, mut limiter) = manual_limiter(Duration::from_secs(1));
+        limiter.set_vsync(Some(&[240.0, 200.0]), 2);
+        assert_eq!(limiter.refresh_interval(), Some(interval));
+        assert_eq!(limiter.vsync_period(), Some(Duration::from_millis(10)));
+        assert_eq!(limiter.frame_duration(), Duration::from_secs(1));
+        assert_eq!(limiter.wait(), Duration::from_millis(10));
+        // 22ms of work misses the vertical blanks at 20, 25 and 30ms.
+        clock.advance(Duration::from_millis(22));
+        assert_eq!(limiter.wait(), Duration::from_millis(25));
+        assert_eq!(limiter.missed_vsyncs(), 3);
+        limiter.set_vsync(None, 1);
+        assert_eq!(limiter.refresh_interval(), None);
+        assert_eq!(limiter.current_frame_duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn frame_time_corridor() {
+        let (clock, mut limiter) = manual_limiter(Duration::from_millis(1));
+        let min = Duration::from_millis(7);
+        let max = Duration::from_millis(25);
+        limiter.set_frame_time_corridor(Some((min, max)));
+        assert_eq!(limiter.wait(), min);
+
+        // A steady load of 12ms per frame moves the deadline within the corridor.
+        for _ in 0..30 {
+            clock.advance(Duration::from_millis(12));
+            assert_eq!(limiter.wait(), Duration::from_millis(12));
+        }
+        let target = limiter.present_deadline() - clock.now();
+        assert!(target > Duration::from_millis(11) && target < Duration::from_millis(12));
+        limiter.set_vsync(Some(&[60.0]), 1);
+        assert_eq!(limiter.frame_time_corridor(), None);
+    }
+
+    #[test]
+    fn background_frame_duration() {
+        let (_clock, mut limiter) = manual_limiter(Duration::from_millis(1));
+        limiter.set_background_frame_duration(Some(Duration::from_millis(20)));
+        assert_eq!(limiter.current_frame_duration(), Duration::from_millis(1));
+        limiter.set_focused(false);
+        assert_eq!(limiter.wait(), Duration::from_millis(20));
+        limiter.set_focused(true);
+        assert_eq!(limiter.current_frame_duration(), Duration::from_millis(1));
+
+        // Idle frames are long, but end early for a timer due soon.
+        limiter.set_idle(true);
+        limiter.set_idle_frame_duration(Duration::from_millis(200));
+        assert_eq!(limiter.current_frame_duration(), Duration::from_millis(200));
+        assert_eq!(limiter.wait(), Duration::from_millis(200));
+        limiter.wake_before(Duration::from_millis(30));
+        assert_eq!(limiter.wait(), Duration::from_millis(30));
+        limiter.set_idle(false);
+        assert_eq!(limiter.current_frame_duration(), Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,63 @@
+//! Personal timelines of individually slowed down or sped up entities.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// The own timeline of an entity running at its own speed on top of a master clock, for
+/// status effects like slow or haste.
+///
+/// Call `update` once per frame with the multiplier of the entity for that frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LocalClock {
+    /// Time elapsed for the entity during the last frame.
+    delta_time: Duration,
+    /// Time elapsed for the entity since it was created.
+    absolute_time: Duration,
+}
+
+impl LocalClock {
+    /// Creates a clock at time zero.
+    pub fn new() -> Self {
+        LocalClock::default()
+    }
+
+    /// Advances the clock by the scaled delta of the master clock times `multiplier`.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn update(&mut self, time: &Time, multiplier: f32) {
+        self.delta_time = time.scaled_delta_for(multiplier);
+        self.absolute_time += self.delta_time;
+    }
+
+    /// Gets the time elapsed for the entity during the last frame.
+    pub fn delta_time(&self) -> Duration {
+        self.delta_time
+    }
+
+    /// Gets the time elapsed for the entity since the clock was created.
+    pub fn absolute_time(&self) -> Duration {
+        self.absolute_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn entity_time_follows_multipliers() {
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        let mut slowed = LocalClock::new();
+        for multiplier in &[1.0, 0.5, 0.0, 4.0] {
+            time.advance_frame(Duration::from_millis(100));
+            slowed.update(&time, *multiplier);
+        }
+        assert_eq!(slowed.delta_time(), Duration::from_millis(200));
+        assert_eq!(slowed.absolute_time(), Duration::from_millis(275));
+        assert_eq!(time.scaled_delta_for(3.0), Duration::from_millis(150));
+    }
+}
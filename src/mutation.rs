@@ -0,0 +1,106 @@
+//! Log of the recent changes made to a clock, for investigating timing bug reports.
+
+use std::time::Duration;
+
+/// A change made to a clock, recorded by its mutation log. See `Time::set_mutation_log_len`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockMutation {
+    /// The effective time scale, including scale layers, changed between two non-zero values.
+    TimeScale {
+        /// Time scale before the change.
+        from: f64,
+        /// Time scale after the change.
+        to: f64,
+    },
+    /// The effective time scale dropped to zero.
+    Paused,
+    /// The effective time scale rose from zero.
+    Unpaused {
+        /// Time scale after the change.
+        scale: f64,
+    },
+    /// A time scale ramp started. The intermediate scales of the ramp are not logged.
+    RampStarted {
+        /// Time scale the ramp moves to.
+        target: f32,
+        /// Real time taken by the ramp.
+        duration: Duration,
+    },
+    /// A scale track started playing. The scales it samples are not logged.
+    TrackStarted,
+    /// The real delta of a frame was replaced by suspend or halt detection.
+    DeltaClamped {
+        /// Real delta given to `advance_frame`.
+        measured: Duration,
+        /// Real delta used instead.
+        used: Duration,
+    },
+    /// Time was removed from the fixed step accumulator and will never be simulated.
+    StepsDropped {
+        /// Time removed.
+        time: Duration,
+    },
+    /// The fixed time step changed.
+    FixedTime {
+        /// Fixed time step before the change.
+        from: Duration,
+        /// Fixed time step after the change.
+        to: Duration,
+    },
+}
+
+/// A `ClockMutation` and the frame during which it happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoggedMutation {
+    /// Frame number of the clock when the change happened.
+    pub frame_number: u64,
+    /// The change.
+    pub mutation: ClockMutation,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn logs_recent_mutations() {
+        let mut time = Time::default();
+        time.set_mutation_log_len(4);
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(16));
+        time.set_time_scale(0.0);
+        time.set_scale_layer("debug", 0.5);
+        time.set_time_scale(1.0);
+        let frames = time
+            .mutation_log()
+            .map(|m| m.frame_number)
+            .collect::<Vec<_>>();
+        assert_eq!(frames, vec![0, 1, 1]);
+        assert_eq!(
+            time.mutation_log().map(|m| m.mutation).collect::<Vec<_>>(),
+            vec![
+                ClockMutation::TimeScale { from: 1.0, to: 2.0 },
+                ClockMutation::Paused,
+                ClockMutation::Unpaused { scale: 0.5 },
+            ]
+        );
+
+        time.ramp_time_scale(4.0, Duration::from_millis(100), Easing::Linear);
+        time.set_halt_threshold(Some(Duration::from_secs(1)));
+        for _ in 0..10 {
+            time.advance_frame(Duration::from_millis(20));
+        }
+        time.advance_frame(Duration::from_secs(5));
+        let last = time.mutation_log().last().unwrap();
+        assert_eq!(last.frame_number, 12);
+        assert_eq!(
+            last.mutation,
+            ClockMutation::DeltaClamped {
+                measured: Duration::from_secs(5),
+                used: Duration::from_millis(20),
+            }
+        );
+        assert_eq!(time.mutation_log().count(), 4);
+    }
+}
@@ -0,0 +1,269 @@
+//! Timing of snapshot-interpolated multiplayer clients.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// Weight of a new sample in the jitter and clock offset estimates, like RTP (RFC 3550).
+const GAIN: f64 = 1.0 / 16.0;
+
+/// Picks the server time to render at from the arrival times of server snapshots, for
+/// clients that interpolate between snapshots.
+///
+/// Rendering a little in the past ("interpolate 100 ms in the past") leaves time for the
+/// next snapshot to arrive. The delay is `buffered_snapshots` snapshot intervals plus twice
+/// the measured arrival jitter, so it grows on bad connections and shrinks back on good ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterpolationWindow {
+    /// Time between two snapshots sent by the server, in nanoseconds.
+    snapshot_interval: u64,
+    buffered_snapshots: u32,
+    /// Estimated arrival jitter, in nanoseconds.
+    jitter: f64,
+    /// Estimated server time minus local real time, in nanoseconds.
+    offset: f64,
+    /// Server time and local real time of the latest snapshot, in nanoseconds.
+    latest: Option<(u64, u64)>,
+}
+
+impl InterpolationWindow {
+    /// Creates a window for a server sending a snapshot every `snapshot_interval`,
+    /// buffering 2 snapshots.
+    ///
+    /// ## Panics
+    /// This will panic if snapshot_interval is zero.
+    pub fn new(snapshot_interval: Duration) -> Self {
+        assert!(snapshot_interval > Duration::from_secs(0));
+        InterpolationWindow {
+            snapshot_interval: crate::to_nanos(snapshot_interval),
+            buffered_snapshots: 2,
+            jitter: 0.0,
+            offset: 0.0,
+            latest: None,
+        }
+    }
+
+    /// Gets the time between two snapshots sent by the server.
+    pub fn snapshot_interval(&self) -> Duration {
+        Duration::from_nanos(self.snapshot_interval)
+    }
+
+    /// Gets the number of snapshot intervals the interpolation delay covers, before jitter.
+    pub fn buffered_snapshots(&self) -> u32 {
+        self.buffered_snapshots
+    }
+
+    /// Sets the number of snapshot intervals the interpolation delay covers, before jitter.
+    /// More snapshots survive more packet loss, at the cost of latency.
+    pub fn set_buffered_snapshots(&mut self, snapshots: u32) {
+        self.buffered_snapshots = snapshots;
+    }
+
+    /// Records a snapshot stamped with `server_time` by the server, arriving now.
+    /// Call it when the snapshot is received, after `advance_frame`.
+    pub fn on_snapshot(&mut self, server_time: Duration, time: &Time) {
+        let server = crate::to_nanos(server_time);
+        let arrival = crate::to_nanos(time.absolute_real_time());
+        let offset = server as f64 - arrival as f64;
+        match self.latest {
+            Some((latest_server, latest_arrival)) => {
+                if server <= latest_server {
+                    // Duplicated or reordered snapshot.
+                    return;
+                }
+                let transit_change = (arrival as f64 - latest_arrival as f64)
+                    - (server as f64 - latest_server as f64);
+                self.jitter += (transit_change.abs() - self.jitter) * GAIN;
+                self.offset += (offset - self.offset) * GAIN;
+            }
+            None => self.offset = offset,
+        }
+        self.latest = Some((server, arrival));
+    }
+
+    /// Gets the estimated arrival jitter of the snapshots.
+    pub fn jitter(&self) -> Duration {
+        Duration::from_nanos(self.jitter as u64)
+    }
+
+    /// Gets how far in the past of the estimated server time rendering happens.
+    pub fn interpolation_delay(&self) -> Duration {
+        let buffered = self.snapshot_interval * u64::from(self.buffered_snapshots);
+        Duration::from_nanos(buffered + (2.0 * self.jitter) as u64)
+    }
+
+    /// Gets how far past the latest snapshot rendering may extrapolate when snapshots are
+    /// late: one snapshot interval plus twice the jitter. Past it, `interpolation_time`
+    /// holds still instead of guessing further.
+    pub fn extrapolation_limit(&self) -> Duration {
+        Duration::from_nanos(self.snapshot_interval + (2.0 * self.jitter) as u64)
+    }
+
+    /// Gets the server time to render at, or None before the first snapshot.
+    pub fn interpolation_time(&self, time: &Time) -> Option<Duration> {
+        let (latest_server, _) = self.latest?;
+        let now = time.absolute_real_time().as_nanos() as f64;
+        let delay = self.interpolation_delay().as_nanos() as f64;
+        let target = (now + self.offset - delay).max(0.0) as u64;
+        let limit = latest_server + crate::to_nanos(self.extrapolation_limit());
+        Some(Duration::from_nanos(target.min(limit)))
+    }
+
+    /// Checks if the interpolation time is past the latest snapshot, so entities have to be
+    /// extrapolated.
+    pub fn is_extrapolating(&self, time: &Time) -> bool {
+        match (self.latest, self.interpolation_time(time)) {
+            (Some((latest_server, _)), Some(at)) => crate::to_nanos(at) > latest_server,
+            _ => false,
+        }
+    }
+}
+
+/// Keeps a client in step with the authoritative server by nudging the speed of its clock,
+/// instead of snapping it and causing a visible hitch.
+///
+/// Each frame, give `update` where the clock is and where it should be on the same timeline,
+/// for example the time of the local fixed step and the server tick plus the desired lead.
+/// The clock then runs up to `max_adjustment` faster or slower, through the scale layer
+/// named `ClockSlew::LAYER`, until the error is within the tolerance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSlew {
+    max_adjustment: f32,
+    /// Time over which an error is corrected, in nanoseconds.
+    correction_time: u64,
+    /// Error under which no adjustment is made, in nanoseconds.
+    tolerance: u64,
+    /// Multiplier applied during the last update.
+    multiplier: f32,
+}
+
+impl ClockSlew {
+    /// Name of the scale layer set by `update`.
+    pub const LAYER: &'static str = "clock_slew";
+
+    /// Creates a controller changing the speed of the clock by at most `max_adjustment`,
+    /// like 0.02 for 2%. Errors are corrected over a second, down to a millisecond.
+    ///
+    /// ## Panics
+    /// This will panic if max_adjustment is NaN or not between 0 and 1.
+    pub fn new(max_adjustment: f32) -> Self {
+        assert!((0.0..1.0).contains(&max_adjustment));
+        ClockSlew {
+            max_adjustment,
+            correction_time: 1_000_000_000,
+            tolerance: 1_000_000,
+            multiplier: 1.0,
+        }
+    }
+
+    /// Gets the largest change of speed, as a fraction of the normal speed.
+    pub fn max_adjustment(&self) -> f32 {
+        self.max_adjustment
+    }
+
+    /// Gets the time over which an error is corrected, when the adjustment isn't capped.
+    pub fn correction_time(&self) -> Duration {
+        Duration::from_nanos(self.correction_time)
+    }
+
+    /// Sets the time over which an error is corrected, when the adjustment isn't capped.
+    ///
+    /// ## Panics
+    /// This will panic if correction_time is zero.
+    pub fn set_correction_time(&mut self, correction_time: Duration) {
+        assert!(correction_time > Duration::from_secs(0));
+        self.correction_time = crate::to_nanos(correction_time);
+    }
+
+    /// Gets the error under which the clock runs at normal speed.
+    pub fn tolerance(&self) -> Duration {
+        Duration::from_nanos(self.tolerance)
+    }
+
+    /// Sets the error under which the clock runs at normal speed.
+    pub fn set_tolerance(&mut self, tolerance: Duration) {
+        self.tolerance = crate::to_nanos(tolerance);
+    }
+
+    /// Gets the speed multiplier applied during the last update.
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    /// Adjusts the speed of `time` so that `local` moves toward `target`.
+    pub fn update(&mut self, local: Duration, target: Duration, time: &mut Time) {
+        let error = target.as_nanos() as f64 - local.as_nanos() as f64;
+        self.multiplier = if error.abs() < self.tolerance as f64 {
+            1.0
+        } else {
+            let max = f64::from(self.max_adjustment);
+            let adjustment = (error / self.correction_time as f64).clamp(-max, max);
+            (1.0 + adjustment) as f32
+        };
+        if time.scale_layer(ClockSlew::LAYER) != Some(self.multiplier) {
+            time.set_scale_layer(ClockSlew::LAYER, self.multiplier);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn delay_follows_jitter() {
+        let mut time = Time::default();
+        let mut window = InterpolationWindow::new(Duration::from_millis(50));
+        assert_eq!(window.interpolation_time(&time), None);
+
+        // Snapshots arrive 30ms after being sent, every 50ms.
+        for tick in 1..=10 {
+            time.advance_frame(Duration::from_millis(50));
+            window.on_snapshot(Duration::from_millis(tick * 50 - 30), &time);
+        }
+        assert_eq!(window.jitter(), Duration::from_secs(0));
+        assert_eq!(window.interpolation_delay(), Duration::from_millis(100));
+        assert_eq!(
+            window.interpolation_time(&time),
+            Some(Duration::from_millis(500 - 30 - 100))
+        );
+
+        // Alternating late and early snapshots raise the delay.
+        for tick in 11..=100 {
+            time.advance_frame(Duration::from_millis(if tick % 2 == 0 { 60 } else { 40 }));
+            window.on_snapshot(Duration::from_millis(tick * 50 - 30), &time);
+        }
+        assert!(window.jitter() > Duration::from_millis(9));
+        assert!(window.interpolation_delay() > Duration::from_millis(118));
+
+        // The server stops sending: extrapolation stops at the limit.
+        time.advance_frame(Duration::from_secs(1));
+        assert!(window.is_extrapolating(&time));
+        assert_eq!(
+            window.interpolation_time(&time),
+            Some(Duration::from_millis(100 * 50 - 30) + window.extrapolation_limit())
+        );
+    }
+
+    #[test]
+    fn slew_toward_server() {
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        let mut slew = ClockSlew::new(0.02);
+        // The client starts 50ms of game time behind where it should be.
+        let lead = Duration::from_millis(50);
+        let target = |time: &Time| time.absolute_real_time() / 2 + lead;
+
+        slew.update(time.absolute_time(), target(&time), &mut time);
+        assert_eq!(slew.multiplier(), 1.02);
+        assert_eq!(time.base_time_scale(), 0.5);
+        for _ in 0..1_000 {
+            time.advance_frame(Duration::from_millis(16));
+            slew.update(time.absolute_time(), target(&time), &mut time);
+        }
+        assert_eq!(slew.multiplier(), 1.0);
+        let error = target(&time) - time.absolute_time();
+        assert!(error < slew.tolerance());
+    }
+}
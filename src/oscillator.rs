@@ -0,0 +1,138 @@
+//! Periodic signals evaluated against game time, for bobbing, pulsing and flashing effects.
+
+use std::f64::consts::TAU;
+use std::time::Duration;
+
+use crate::Time;
+
+/// Shape of the signal of an `Oscillator`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Waveform {
+    /// Smooth wave, starting at 0 and rising.
+    #[default]
+    Sine,
+    /// Rises linearly from -1 to 1 over the first half of the period, then falls back.
+    Triangle,
+    /// 1 during the first half of the period, -1 during the second half.
+    Square,
+    /// Rises linearly from -1 to 1 over the period, then jumps back.
+    Sawtooth,
+}
+
+/// A periodic signal between -1 and 1.
+///
+/// Because it is evaluated against `Time::absolute_time`, it slows down with the time scale
+/// and stops while the game is paused.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oscillator {
+    waveform: Waveform,
+    /// Scaled game time taken by one cycle.
+    period: Duration,
+    /// Offset of the signal, as a fraction of the period.
+    phase: f32,
+}
+
+impl Oscillator {
+    /// Creates an oscillator completing one cycle every `period` of scaled game time.
+    ///
+    /// ## Panics
+    /// This will panic if period is zero.
+    pub fn new(waveform: Waveform, period: Duration) -> Self {
+        assert!(period > Duration::from_secs(0));
+        Oscillator {
+            waveform,
+            period,
+            phase: 0.0,
+        }
+    }
+
+    /// Gets the shape of the signal.
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    /// Gets the scaled game time taken by one cycle.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Gets the offset of the signal, as a fraction of the period.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Sets the offset of the signal, as a fraction of the period. For example, giving
+    /// pickups different phases keeps them from bobbing in unison.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase;
+    }
+
+    /// Gets the value of the signal at the scaled absolute time of the clock.
+    pub fn value(&self, time: &Time) -> f32 {
+        self.value_at(time.absolute_time())
+    }
+
+    /// Gets the value of the signal at the given time.
+    pub fn value_at(&self, time: Duration) -> f32 {
+        let period = self.period.as_nanos();
+        let cycle = (time.as_nanos() % period) as f64 / period as f64;
+        let cycle = (cycle + f64::from(self.phase)).rem_euclid(1.0);
+        let value = match self.waveform {
+            Waveform::Sine => (cycle * TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (cycle - 0.5).abs(),
+            Waveform::Square => {
+                if cycle < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sawtooth => 2.0 * cycle - 1.0,
+        };
+        value as f32
+    }
+
+    /// Gets the value of the signal at the scaled absolute time of the clock, remapped
+    /// between 0 and 1.
+    pub fn unit_value(&self, time: &Time) -> f32 {
+        (self.value(time) + 1.0) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn waveforms() {
+        let period = Duration::from_secs(4);
+        let at = Duration::from_secs(1);
+        assert!((Oscillator::new(Waveform::Sine, period).value_at(at) - 1.0).abs() < 1e-6);
+        assert_eq!(
+            Oscillator::new(Waveform::Triangle, period).value_at(at),
+            0.0
+        );
+        assert_eq!(Oscillator::new(Waveform::Square, period).value_at(at), 1.0);
+        assert_eq!(
+            Oscillator::new(Waveform::Sawtooth, period).value_at(at),
+            -0.5
+        );
+
+        let mut shifted = Oscillator::new(Waveform::Square, period);
+        shifted.set_phase(0.5);
+        assert_eq!(shifted.value_at(at), -1.0);
+    }
+
+    #[test]
+    fn stops_while_paused() {
+        let oscillator = Oscillator::new(Waveform::Sawtooth, Duration::from_secs(2));
+        let mut time = Time::default();
+        time.advance_frame(Duration::from_millis(500));
+        let value = oscillator.value(&time);
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_millis(500));
+        assert_eq!(oscillator.value(&time), value);
+        assert_eq!(oscillator.unit_value(&time), 0.25);
+    }
+}
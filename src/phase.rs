@@ -0,0 +1,59 @@
+//! Fixed update phase, letting systems shared with the variable update read the right delta.
+
+use std::ops::Deref;
+
+use crate::Time;
+
+/// Access to a clock during one fixed step, obtained from `Time::enter_fixed_update`.
+///
+/// While the guard lives, `delta_time` returns `fixed_time` and `in_fixed_update` is true,
+/// so a system can be written once and run in both the variable and the fixed update.
+/// Dropping the guard leaves the fixed update phase.
+#[derive(Debug)]
+pub struct FixedUpdateGuard<'a> {
+    time: &'a mut Time,
+}
+
+impl<'a> FixedUpdateGuard<'a> {
+    pub(crate) fn new(time: &'a mut Time) -> Self {
+        time.fixed_phase = true;
+        FixedUpdateGuard { time }
+    }
+}
+
+impl Deref for FixedUpdateGuard<'_> {
+    type Target = Time;
+
+    fn deref(&self) -> &Time {
+        self.time
+    }
+}
+
+impl Drop for FixedUpdateGuard<'_> {
+    fn drop(&mut self) {
+        self.time.fixed_phase = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    fn system_delta(time: &Time) -> (Duration, bool) {
+        (time.delta_time(), time.in_fixed_update())
+    }
+
+    #[test]
+    fn fixed_phase_delta() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        let mut steps = vec![];
+        while let Some(fixed) = time.enter_fixed_update() {
+            steps.push(system_delta(&fixed));
+        }
+        assert_eq!(steps, vec![(Duration::from_millis(10), true); 2]);
+        assert_eq!(system_delta(&time), (Duration::from_millis(25), false));
+    }
+}
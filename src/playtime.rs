@@ -0,0 +1,167 @@
+//! Total time played, accumulated across sessions.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::Time;
+
+/// Which timeline a `Playtime` accumulates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaytimeSource {
+    /// Real time, ignoring the time speed multiplier.
+    #[default]
+    Real,
+    /// Scaled game time.
+    Scaled,
+}
+
+/// Accumulates the total time played, for the "time played" stat of save files.
+///
+/// Seed it with the value read from the save file, call `update` once per frame, and write
+/// `as_nanos` back into the save file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Playtime {
+    /// Time played, in nanoseconds.
+    total: u64,
+    source: PlaytimeSource,
+    /// True if frames where time is stopped (scale of 0) are not counted.
+    exclude_paused: bool,
+    /// True if frames excluded by `Time::begin_excluded_section` are not counted.
+    exclude_sections: bool,
+}
+
+impl Playtime {
+    /// Creates a playtime of zero, accumulating real time, including pauses.
+    pub fn new() -> Self {
+        Playtime::from_nanos(0)
+    }
+
+    /// Creates a playtime starting at the given number of nanoseconds, as saved by `as_nanos`.
+    pub fn from_nanos(nanos: u64) -> Self {
+        Playtime {
+            total: nanos,
+            source: PlaytimeSource::default(),
+            exclude_paused: false,
+            exclude_sections: false,
+        }
+    }
+
+    /// Gets the time played.
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.total)
+    }
+
+    /// Gets the time played in nanoseconds, for storing it in a save file.
+    pub fn as_nanos(&self) -> u64 {
+        self.total
+    }
+
+    /// Gets which timeline is accumulated.
+    pub fn source(&self) -> PlaytimeSource {
+        self.source
+    }
+
+    /// Sets which timeline is accumulated.
+    pub fn set_source(&mut self, source: PlaytimeSource) {
+        self.source = source;
+    }
+
+    /// Checks if frames where time is stopped are not counted.
+    pub fn exclude_paused(&self) -> bool {
+        self.exclude_paused
+    }
+
+    /// Sets whether frames where time is stopped (scale of 0, or frozen while unfocused) are
+    /// not counted.
+    pub fn set_exclude_paused(&mut self, exclude: bool) {
+        self.exclude_paused = exclude;
+    }
+
+    /// Checks if frames excluded by `Time::begin_excluded_section` are not counted.
+    pub fn exclude_sections(&self) -> bool {
+        self.exclude_sections
+    }
+
+    /// Sets whether frames excluded by `Time::begin_excluded_section`, like loading screens,
+    /// are not counted.
+    pub fn set_exclude_sections(&mut self, exclude: bool) {
+        self.exclude_sections = exclude;
+    }
+
+    /// Adds the current frame of the clock. Call once per frame, after `advance_frame`.
+    pub fn update(&mut self, time: &Time) {
+        if self.exclude_paused && time.time_scale_f64() == 0.0
+            || self.exclude_sections && time.is_frame_excluded()
+        {
+            return;
+        }
+        let delta = match self.source {
+            PlaytimeSource::Real => time.delta_real_time(),
+            PlaytimeSource::Scaled => time.delta_time(),
+        };
+        self.add(delta);
+    }
+
+    /// Adds the given duration to the time played.
+    pub fn add(&mut self, duration: Duration) {
+        self.total = self.total.saturating_add(crate::to_nanos(duration));
+    }
+}
+
+impl Default for Playtime {
+    fn default() -> Self {
+        Playtime::new()
+    }
+}
+
+/// Formats the time played as hours and minutes, like `83h 12m`.
+impl fmt::Display for Playtime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.total().as_secs() / 60;
+        write!(f, "{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn accumulates_across_sessions() {
+        let saved = Duration::from_secs(83 * 3_600 + 11 * 60 + 30).as_nanos() as u64;
+        let mut playtime = Playtime::from_nanos(saved);
+        playtime.set_exclude_paused(true);
+        let mut time = Time::default();
+
+        time.advance_frame(Duration::from_secs(40));
+        playtime.update(&time);
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(600));
+        playtime.update(&time);
+
+        assert_eq!(playtime.to_string(), "83h 12m");
+        assert_eq!(
+            Playtime::from_nanos(playtime.as_nanos()).total(),
+            Duration::from_secs(83 * 3_600 + 12 * 60 + 10)
+        );
+    }
+
+    #[test]
+    fn scaled_source() {
+        let mut playtime = Playtime::new();
+        playtime.set_source(PlaytimeSource::Scaled);
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_secs(1));
+        playtime.update(&time);
+        assert_eq!(playtime.total(), Duration::from_secs(2));
+
+        playtime.set_source(PlaytimeSource::Real);
+        playtime.set_exclude_sections(true);
+        time.begin_excluded_section();
+        time.advance_frame(Duration::from_secs(5));
+        playtime.update(&time);
+        assert_eq!(playtime.total(), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,96 @@
+//! Time dilation zones: areas of the world where time runs at a different speed.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// An area of the world where time runs at a different speed, like a slow-motion bubble.
+///
+/// The game decides which regions an entity is in, and gets the delta of the entity from
+/// `RegionBlend::delta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaleRegion {
+    multiplier: f32,
+}
+
+impl ScaleRegion {
+    /// Creates a region where time runs `multiplier` times as fast as in the rest of the world.
+    ///
+    /// ## Panics
+    /// This will panic if multiplier is NaN, Infinity, or less than 0.
+    pub fn new(multiplier: f32) -> Self {
+        assert!(multiplier >= 0.0);
+        assert!(multiplier != f32::INFINITY);
+        ScaleRegion { multiplier }
+    }
+
+    /// Gets how many times as fast time runs in the region.
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+}
+
+/// How the multipliers of overlapping regions are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RegionBlend {
+    /// Multiplies every multiplier: two half-speed bubbles give quarter speed.
+    #[default]
+    Multiply,
+    /// Uses the smallest multiplier.
+    Slowest,
+    /// Uses the largest multiplier.
+    Fastest,
+}
+
+impl RegionBlend {
+    /// Gets the multiplier of an entity in the given regions. Entities outside of every region
+    /// get 1.
+    pub fn combine<'a>(self, regions: impl IntoIterator<Item = &'a ScaleRegion>) -> f32 {
+        let multipliers = regions.into_iter().map(|region| region.multiplier);
+        match self {
+            RegionBlend::Multiply => multipliers.product(),
+            RegionBlend::Slowest => multipliers.reduce(f32::min).unwrap_or(1.0),
+            RegionBlend::Fastest => multipliers.reduce(f32::max).unwrap_or(1.0),
+        }
+    }
+
+    /// Gets the delta of an entity in the given regions: the scaled delta of the clock times
+    /// the combined multiplier of the regions.
+    pub fn delta<'a>(
+        self,
+        time: &Time,
+        regions: impl IntoIterator<Item = &'a ScaleRegion>,
+    ) -> Duration {
+        time.scaled_delta_for(self.combine(regions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn overlapping_regions() {
+        let bubble = ScaleRegion::new(0.5);
+        let haste = ScaleRegion::new(2.0);
+        let frozen = ScaleRegion::new(0.0);
+        let both = [bubble, bubble, haste];
+        assert_eq!(RegionBlend::Multiply.combine(&both), 0.5);
+        assert_eq!(RegionBlend::Slowest.combine(&both), 0.5);
+        assert_eq!(RegionBlend::Fastest.combine(&both), 2.0);
+        assert_eq!(RegionBlend::Slowest.combine(&[]), 1.0);
+
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(10));
+        assert_eq!(
+            RegionBlend::Multiply.delta(&time, &[bubble]),
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            RegionBlend::Multiply.delta(&time, &[bubble, frozen]),
+            Duration::from_secs(0)
+        );
+    }
+}
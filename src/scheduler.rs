@@ -0,0 +1,747 @@
+//! Delayed calls driven by the game clock.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+use crate::rng::SplitMix64;
+use crate::wheel::TimerWheel;
+use crate::{to_nanos, Time};
+
+/// Data structure used by a `Scheduler` to order its pending calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchedulerBackend {
+    /// A binary heap. O(log n) insertion and expiration, good for up to a few thousand calls.
+    #[default]
+    BinaryHeap,
+    /// A hierarchical timer wheel. O(1) insertion and expiration, suited to tens of
+    /// thousands of pending calls.
+    /// Calls expire in due order, but never before the tick of `resolution` they fall in
+    /// has been reached.
+    TimerWheel {
+        /// Length of a tick of the wheel.
+        resolution: Duration,
+    },
+}
+
+/// Timeline followed by a `Scheduler`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TimerMode {
+    /// Scaled game time: calls slow down with the time scale and freeze while paused.
+    #[default]
+    GameTime,
+    /// Real time: calls keep running at wall clock speed whatever the time scale, for UI
+    /// timeouts, toast notifications and connection timeouts.
+    RealTime,
+}
+
+/// Pending due times, as `(due, id)` pairs.
+/// Can contain stale items for cancelled and rescheduled calls.
+enum Queue {
+    Heap(BinaryHeap<Reverse<(Duration, u64)>>),
+    Wheel(TimerWheel),
+}
+
+impl Queue {
+    fn new(backend: SchedulerBackend) -> Self {
+        match backend {
+            SchedulerBackend::BinaryHeap => Queue::Heap(BinaryHeap::new()),
+            SchedulerBackend::TimerWheel { resolution } => {
+                Queue::Wheel(TimerWheel::new(to_nanos(resolution)))
+            }
+        }
+    }
+
+    fn push(&mut self, due: Duration, id: u64) {
+        match self {
+            Queue::Heap(heap) => heap.push(Reverse((due, id))),
+            Queue::Wheel(wheel) => wheel.push(to_nanos(due), id),
+        }
+    }
+
+    /// Gets the due time of the soonest item for which `live` returns true. Stale items found
+    /// on the way are removed, and counted in `removed`.
+    fn peek(
+        &mut self,
+        live: impl Fn(Duration, u64) -> bool,
+        removed: &mut usize,
+    ) -> Option<Duration> {
+        match self {
+            Queue::Heap(heap) => {
+                while let Some(&Reverse((due, id))) = heap.peek() {
+                    if live(due, id) {
+                        return Some(due);
+                    }
+                    heap.pop();
+                    *removed += 1;
+                }
+                None
+            }
+            Queue::Wheel(wheel) => wheel
+                .peek(|due, id| live(Duration::from_nanos(due), id), removed)
+                .map(Duration::from_nanos),
+        }
+    }
+
+    /// Keeps only the items for which `keep` returns true.
+    fn retain(&mut self, mut keep: impl FnMut(Duration, u64) -> bool) {
+        match self {
+            Queue::Heap(heap) => heap.retain(|Reverse((due, id))| keep(*due, *id)),
+            Queue::Wheel(wheel) => wheel.retain(|due, id| keep(Duration::from_nanos(due), id)),
+        }
+    }
+
+    /// Removes the soonest item due at or before `now`.
+    fn pop_due(&mut self, now: Duration) -> Option<(Duration, u64)> {
+        match self {
+            Queue::Heap(heap) => match heap.peek() {
+                Some(&Reverse((due, id))) if due <= now => {
+                    heap.pop();
+                    Some((due, id))
+                }
+                _ => None,
+            },
+            Queue::Wheel(wheel) => wheel
+                .pop_due(to_nanos(now))
+                .map(|(due, id)| (Duration::from_nanos(due), id)),
+        }
+    }
+}
+
+/// Identifies a call registered in a `Scheduler`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(u64);
+
+impl Handle {
+    /// Gets the raw value of the handle, to store it in a save file.
+    pub fn to_raw(self) -> u64 {
+        self.0
+    }
+
+    /// Rebuilds a handle from the value returned by `to_raw`.
+    pub fn from_raw(raw: u64) -> Self {
+        Handle(raw)
+    }
+}
+
+/// A pending call of a `Scheduler`, without its closure. See `Scheduler::state`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PendingCall {
+    /// Raw value of the handle of the call.
+    pub handle: u64,
+    /// Value set with `Scheduler::set_payload`, telling which closure to bind after loading.
+    pub payload: Option<u64>,
+    /// Time at which the call runs, in nanoseconds.
+    pub due_nanos: u64,
+    /// Period of a repeating call, in nanoseconds, or 0 for a call that runs once.
+    pub interval_nanos: u64,
+    /// Jitter of a repeating call. See `Scheduler::every_with_jitter`.
+    pub jitter: f32,
+}
+
+/// The pending calls of a `Scheduler`, as plain values that can be written in a save file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchedulerState {
+    /// Current time of the scheduler, in nanoseconds.
+    pub now_nanos: u64,
+    /// Timeline the scheduler follows.
+    pub mode: TimerMode,
+    /// Raw value of the handle given to the next scheduled call.
+    pub next_handle: u64,
+    /// State of the generator of the jitter, so that it continues the same sequence.
+    pub rng_state: u64,
+    /// Pending calls, ordered by handle.
+    pub calls: Vec<PendingCall>,
+}
+
+enum Callback<C> {
+    Once(Box<dyn FnOnce(&mut C)>),
+    Repeat(Box<dyn FnMut(&mut C)>),
+}
+
+/// Period of a repeating call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Repeat {
+    interval: Duration,
+    /// Fraction of `interval` by which each period is randomly lengthened or shortened.
+    jitter: f32,
+}
+
+struct Entry<C> {
+    due: Duration,
+    repeat: Option<Repeat>,
+    payload: Option<u64>,
+    callback: Callback<C>,
+}
+
+/// Runs closures once a given amount of game time has passed.
+///
+/// The scheduler follows the scaled timeline of a `Time`, or its real timeline in
+/// `TimerMode::RealTime`: call `update` after advancing the clock, then `run_due` at the
+/// point of the frame where the delayed calls should run.
+/// `C` is the context handed to every closure when it runs.
+pub struct Scheduler<C> {
+    /// Current time of the scheduler, copied from the clock.
+    now: Duration,
+    /// Timeline of the clock the scheduler follows.
+    mode: TimerMode,
+    /// Id given to the next scheduled call.
+    next_id: u64,
+    /// Pending calls, by handle id.
+    entries: HashMap<u64, Entry<C>>,
+    /// Due times of pending calls.
+    queue: Queue,
+    /// Number of items of `queue` left behind by `cancel` and `reschedule`.
+    stale: usize,
+    /// Source of the jitter applied to repeating calls.
+    rng: SplitMix64,
+}
+
+impl<C> Scheduler<C> {
+    /// Creates an empty scheduler starting at time zero.
+    /// Jitter on repeating calls is randomly seeded.
+    pub fn new() -> Self {
+        Scheduler::with_backend(SchedulerBackend::default())
+    }
+
+    /// Creates an empty scheduler starting at time zero, storing its pending calls
+    /// in the given backend.
+    pub fn with_backend(backend: SchedulerBackend) -> Self {
+        Scheduler::with_rng(backend, SplitMix64::from_entropy())
+    }
+
+    /// Creates an empty scheduler storing its pending calls in the given backend,
+    /// with deterministic jitter. See `Scheduler::with_seed`.
+    pub fn with_backend_and_seed(backend: SchedulerBackend, seed: u64) -> Self {
+        Scheduler::with_rng(backend, SplitMix64::new(seed))
+    }
+
+    /// Creates an empty scheduler starting at time zero, whose jitter on repeating calls
+    /// is derived from `seed`. The same seed and calls always produce the same schedule,
+    /// which keeps replays deterministic.
+    pub fn with_seed(seed: u64) -> Self {
+        Scheduler::with_backend_and_seed(SchedulerBackend::default(), seed)
+    }
+
+    fn with_rng(backend: SchedulerBackend, rng: SplitMix64) -> Self {
+        Scheduler {
+            now: Duration::from_secs(0),
+            mode: TimerMode::default(),
+            next_id: 0,
+            entries: HashMap::new(),
+            queue: Queue::new(backend),
+            stale: 0,
+            rng,
+        }
+    }
+
+    /// Gets the current time of the scheduler.
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Gets the timeline the scheduler follows.
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    /// Sets the timeline the scheduler follows. Defaults to `TimerMode::GameTime`.
+    /// Change it before scheduling any call: due times are not converted.
+    pub fn set_mode(&mut self, mode: TimerMode) {
+        self.mode = mode;
+    }
+
+    /// Gets the number of pending calls.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if there are no pending calls.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Synchronizes the scheduler with the absolute time of the clock, scaled or real
+    /// depending on the mode.
+    pub fn update(&mut self, time: &Time) {
+        self.now = match self.mode {
+            TimerMode::GameTime => time.absolute_time(),
+            TimerMode::RealTime => time.absolute_real_time(),
+        };
+    }
+
+    /// Schedules `callback` to run once `delay` has passed.
+    pub fn schedule_after<F>(&mut self, delay: Duration, callback: F) -> Handle
+    where
+        F: FnOnce(&mut C) + 'static,
+    {
+        self.insert(delay, None, Callback::Once(Box::new(callback)))
+    }
+
+    /// Schedules `callback` to run every `interval`, starting one interval from now.
+    ///
+    /// ## Panics
+    /// This will panic if interval is zero.
+    pub fn every<F>(&mut self, interval: Duration, callback: F) -> Handle
+    where
+        F: FnMut(&mut C) + 'static,
+    {
+        self.every_with_jitter(interval, 0.0, callback)
+    }
+
+    /// Schedules `callback` to run repeatedly, each period being `interval` randomly lengthened
+    /// or shortened by up to `jitter` times the interval.
+    ///
+    /// Jitter spreads periodic work (like the AI ticks of many agents started on the same frame)
+    /// over different frames. Use `Scheduler::with_seed` to get the same jitter on every run.
+    ///
+    /// ## Panics
+    /// This will panic if interval is zero or if jitter is not between 0 and 1.
+    pub fn every_with_jitter<F>(&mut self, interval: Duration, jitter: f32, callback: F) -> Handle
+    where
+        F: FnMut(&mut C) + 'static,
+    {
+        assert!(interval > Duration::from_secs(0));
+        assert!((0.0..=1.0).contains(&jitter));
+        let repeat = Repeat { interval, jitter };
+        let delay = self.period(repeat);
+        self.insert(delay, Some(repeat), Callback::Repeat(Box::new(callback)))
+    }
+
+    fn insert(&mut self, delay: Duration, repeat: Option<Repeat>, callback: Callback<C>) -> Handle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let due = self.now.saturating_add(delay);
+        self.entries.insert(
+            id,
+            Entry {
+                due,
+                repeat,
+                payload: None,
+                callback,
+            },
+        );
+        self.queue.push(due, id);
+        Handle(id)
+    }
+
+    /// Computes the length of the next period of a repeating call.
+    fn period(&mut self, repeat: Repeat) -> Duration {
+        if repeat.jitter == 0.0 {
+            return repeat.interval;
+        }
+        let offset = (self.rng.next_f64() * 2.0 - 1.0) * f64::from(repeat.jitter);
+        // Never let a period collapse to zero, which would run the call forever.
+        repeat
+            .interval
+            .mul_f64(1.0 + offset)
+            .max(Duration::from_nanos(1))
+    }
+
+    /// Checks if the call is still waiting to run.
+    pub fn is_scheduled(&self, handle: Handle) -> bool {
+        self.entries.contains_key(&handle.0)
+    }
+
+    /// Gets the time at which the call will run, if it is still pending.
+    pub fn due_time(&self, handle: Handle) -> Option<Duration> {
+        self.entries.get(&handle.0).map(|entry| entry.due)
+    }
+
+    /// Gets the time at which the soonest pending call will run, or None if no call is
+    /// pending.
+    ///
+    /// Looks at the head of the backend, dropping the items left behind by `cancel` and
+    /// `reschedule` that come before the soonest pending call.
+    pub fn next_due(&mut self) -> Option<Duration> {
+        let entries = &self.entries;
+        let mut removed = 0;
+        let next = self.queue.peek(
+            |due, id| entries.get(&id).map(|entry| entry.due) == Some(due),
+            &mut removed,
+        );
+        self.stale = self.stale.saturating_sub(removed);
+        next
+    }
+
+    /// Gets the time left until the soonest pending call runs, or zero if it is already due.
+    ///
+    /// Headless servers can sleep that long instead of polling every tick. In
+    /// `TimerMode::GameTime`, divide it by the time scale of the clock to get the real delay.
+    pub fn time_until_next_due(&mut self) -> Option<Duration> {
+        let now = self.now;
+        self.next_due()
+            .map(|due| due.checked_sub(now).unwrap_or_default())
+    }
+
+    /// Gets the payload of a pending call. See `set_payload`.
+    pub fn payload(&self, handle: Handle) -> Option<u64> {
+        self.entries.get(&handle.0).and_then(|entry| entry.payload)
+    }
+
+    /// Tags a pending call with a payload id, saved by `state` in place of its closure and
+    /// used to bind it again in `from_state`. Returns false if the call already ran or was
+    /// cancelled.
+    pub fn set_payload(&mut self, handle: Handle, payload: u64) -> bool {
+        match self.entries.get_mut(&handle.0) {
+            Some(entry) => {
+                entry.payload = Some(payload);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gets the pending calls, without their closures, to save them.
+    /// Calls keep their due times, so partially elapsed cooldowns resume where they were.
+    pub fn state(&self) -> SchedulerState {
+        let mut calls = self
+            .entries
+            .iter()
+            .map(|(id, entry)| PendingCall {
+                handle: *id,
+                payload: entry.payload,
+                due_nanos: to_nanos(entry.due),
+                interval_nanos: entry.repeat.map_or(0, |repeat| to_nanos(repeat.interval)),
+                jitter: entry.repeat.map_or(0.0, |repeat| repeat.jitter),
+            })
+            .collect::<Vec<_>>();
+        calls.sort_by_key(|call| call.handle);
+        SchedulerState {
+            now_nanos: to_nanos(self.now),
+            mode: self.mode,
+            next_handle: self.next_id,
+            rng_state: self.rng.state(),
+            calls,
+        }
+    }
+
+    /// Restores a scheduler saved by `state`, storing its calls in the given backend.
+    ///
+    /// `bind` is called for every saved call and returns its closure, usually picked from
+    /// the payload. Calls for which it returns `None` are dropped. Handles stay valid.
+    pub fn from_state<F>(backend: SchedulerBackend, state: &SchedulerState, mut bind: F) -> Self
+    where
+        F: FnMut(&PendingCall) -> Option<Box<dyn FnMut(&mut C)>>,
+    {
+        let mut scheduler = Scheduler::with_rng(backend, SplitMix64::new(state.rng_state));
+        scheduler.now = Duration::from_nanos(state.now_nanos);
+        scheduler.mode = state.mode;
+        scheduler.next_id = state.next_handle;
+        for call in &state.calls {
+            if let Some(callback) = bind(call) {
+                let repeat = if call.interval_nanos > 0 {
+                    Some(Repeat {
+                        interval: Duration::from_nanos(call.interval_nanos),
+                        jitter: call.jitter,
+                    })
+                } else {
+                    None
+                };
+                let due = Duration::from_nanos(call.due_nanos);
+                scheduler.entries.insert(
+                    call.handle,
+                    Entry {
+                        due,
+                        repeat,
+                        payload: call.payload,
+                        // Runs once when there is no repeat.
+                        callback: Callback::Repeat(callback),
+                    },
+                );
+                scheduler.queue.push(due, call.handle);
+            }
+        }
+        scheduler
+    }
+
+    /// Cancels a pending call. Returns false if it already ran or was cancelled.
+    pub fn cancel(&mut self, handle: Handle) -> bool {
+        let cancelled = self.entries.remove(&handle.0).is_some();
+        if cancelled {
+            self.add_stale();
+        }
+        cancelled
+    }
+
+    /// Moves a pending call so that it runs `delay` from now.
+    /// Returns false if it already ran or was cancelled.
+    pub fn reschedule(&mut self, handle: Handle, delay: Duration) -> bool {
+        let due = self.now.saturating_add(delay);
+        match self.entries.get_mut(&handle.0) {
+            Some(entry) => {
+                entry.due = due;
+                self.queue.push(due, handle.0);
+                self.add_stale();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts an item of the queue that no longer matches a pending call, and drops them all
+    /// once they outnumber the pending calls, so that cancelling and rescheduling calls that
+    /// never come due doesn't grow the queue forever.
+    fn add_stale(&mut self) {
+        self.stale += 1;
+        if self.stale > self.entries.len() {
+            let entries = &self.entries;
+            self.queue
+                .retain(|due, id| entries.get(&id).map(|entry| entry.due) == Some(due));
+            self.stale = 0;
+        }
+    }
+
+    /// Runs every call that is due, in order of due time, and returns how many ran.
+    /// Calls due at the same time run in the order they were scheduled.
+    /// A repeating call runs once for each of its periods that elapsed.
+    pub fn run_due(&mut self, ctx: &mut C) -> usize {
+        let mut ran = 0;
+        while let Some((due, id)) = self.queue.pop_due(self.now) {
+            if self.entries.get(&id).map(|entry| entry.due) != Some(due) {
+                // Stale item left behind by `cancel` or `reschedule`.
+                self.stale = self.stale.saturating_sub(1);
+                continue;
+            }
+            if let Some(mut entry) = self.entries.remove(&id) {
+                ran += 1;
+                match entry.callback {
+                    Callback::Once(callback) => callback(ctx),
+                    Callback::Repeat(ref mut callback) => {
+                        callback(ctx);
+                        if let Some(repeat) = entry.repeat {
+                            entry.due = due.saturating_add(self.period(repeat));
+                            self.queue.push(entry.due, id);
+                            self.entries.insert(id, entry);
+                        }
+                    }
+                }
+            }
+        }
+        ran
+    }
+}
+
+impl<C> Default for Scheduler<C> {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+impl<C> fmt::Debug for Scheduler<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("now", &self.now)
+            .field("mode", &self.mode)
+            .field("pending", &self.entries.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn runs_due_calls_in_order() {
+        let mut time = Time::default();
+        let mut scheduler = Scheduler::<Vec<u32>>::new();
+        scheduler.schedule_after(Duration::from_millis(20), |log| log.push(2));
+        scheduler.schedule_after(Duration::from_millis(10), |log| log.push(1));
+        scheduler.schedule_after(Duration::from_millis(50), |log| log.push(3));
+
+        assert_eq!(scheduler.next_due(), Some(Duration::from_millis(10)));
+
+        let mut log = vec![];
+        time.advance_frame(Duration::from_millis(30));
+        scheduler.update(&time);
+        assert_eq!(
+            scheduler.time_until_next_due(),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(scheduler.run_due(&mut log), 2);
+        assert_eq!(log, vec![1, 2]);
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(
+            scheduler.time_until_next_due(),
+            Some(Duration::from_millis(20))
+        );
+        time.advance_frame(Duration::from_millis(30));
+        scheduler.update(&time);
+        scheduler.run_due(&mut log);
+        assert_eq!(scheduler.next_due(), None);
+    }
+
+    #[test]
+    fn repeating_calls() {
+        let mut time = Time::default();
+        let mut scheduler = Scheduler::<u32>::new();
+        let handle = scheduler.every(Duration::from_millis(10), |n| *n += 1);
+
+        let mut count = 0;
+        time.advance_frame(Duration::from_millis(35));
+        scheduler.update(&time);
+        assert_eq!(scheduler.run_due(&mut count), 3);
+        assert_eq!(scheduler.due_time(handle), Some(Duration::from_millis(40)));
+
+        scheduler.cancel(handle);
+        time.advance_frame(Duration::from_millis(35));
+        scheduler.update(&time);
+        scheduler.run_due(&mut count);
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn seeded_jitter_is_deterministic() {
+        let due_times = |seed| {
+            let mut scheduler = Scheduler::<()>::with_seed(seed);
+            (0..10)
+                .map(|_| scheduler.every_with_jitter(Duration::from_secs(1), 0.5, |_| {}))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| scheduler.due_time(handle).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let first = due_times(7);
+        assert_eq!(first, due_times(7));
+        assert!(first.iter().any(|due| *due != first[0]));
+        assert!(first
+            .iter()
+            .all(|due| *due >= Duration::from_millis(500) && *due <= Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn timer_wheel_backend() {
+        let mut time = Time::default();
+        let backend = SchedulerBackend::TimerWheel {
+            resolution: Duration::from_millis(1),
+        };
+        let mut scheduler = Scheduler::<Vec<u64>>::with_backend(backend);
+        for i in (0..10_000).rev() {
+            scheduler.schedule_after(Duration::from_millis(i * 7), move |log| log.push(i));
+        }
+        let handle = scheduler.every(Duration::from_secs(1), |_| {});
+        scheduler.cancel(handle);
+        assert_eq!(scheduler.next_due(), Some(Duration::from_secs(0)));
+
+        let mut log = vec![];
+        time.advance_frame(Duration::from_secs(35));
+        scheduler.update(&time);
+        scheduler.run_due(&mut log);
+        assert_eq!(log, (0..=5_000).collect::<Vec<_>>());
+
+        time.advance_frame(Duration::from_secs(35));
+        scheduler.update(&time);
+        scheduler.run_due(&mut log);
+        assert_eq!(log.len(), 10_000);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn real_time_ignores_pause() {
+        let mut time = Time::default();
+        let mut scheduler = Scheduler::<u32>::new();
+        scheduler.set_mode(TimerMode::RealTime);
+        scheduler.schedule_after(Duration::from_secs(3), |n| *n += 1);
+
+        let mut count = 0;
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(2));
+        scheduler.update(&time);
+        scheduler.run_due(&mut count);
+        assert_eq!(count, 0);
+        time.set_time_scale(0.5);
+        time.advance_frame(Duration::from_secs(1));
+        scheduler.update(&time);
+        scheduler.run_due(&mut count);
+        assert_eq!(count, 1);
+        assert_eq!(scheduler.now(), time.absolute_real_time());
+    }
+
+    #[test]
+    fn saved_calls_resume() {
+        let mut time = Time::default();
+        let mut scheduler = Scheduler::<u32>::with_seed(3);
+        let cooldown = scheduler.schedule_after(Duration::from_secs(10), |n| *n += 1);
+        scheduler.set_payload(cooldown, 1);
+        let regen = scheduler.every_with_jitter(Duration::from_secs(1), 0.2, |n| *n += 10);
+        scheduler.set_payload(regen, 2);
+        scheduler.schedule_after(Duration::from_secs(1), |_| {});
+        time.advance_frame(Duration::from_secs(4));
+        scheduler.update(&time);
+
+        let state = scheduler.state();
+        assert_eq!(state.calls.len(), 3);
+        let mut loaded =
+            Scheduler::<u32>::from_state(SchedulerBackend::BinaryHeap, &state, |call| {
+                match call.payload {
+                    Some(1) => Some(Box::new(|n: &mut u32| *n += 1)),
+                    Some(2) => Some(Box::new(|n: &mut u32| *n += 10)),
+                    _ => None,
+                }
+            });
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.due_time(cooldown), Some(Duration::from_secs(10)));
+        assert_eq!(loaded.payload(regen), Some(2));
+
+        let (mut expected, mut actual) = (0, 0);
+        scheduler.cancel(Handle::from_raw(2));
+        for _ in 0..8 {
+            time.advance_frame(Duration::from_secs(1));
+            scheduler.update(&time);
+            loaded.update(&time);
+            scheduler.run_due(&mut expected);
+            loaded.run_due(&mut actual);
+        }
+        assert_eq!(actual, expected);
+        assert!(!loaded.is_scheduled(cooldown));
+        assert_eq!(loaded.due_time(regen), scheduler.due_time(regen));
+
+        // Calls due beyond what nanoseconds can hold are saved as due at the end of time.
+        let never = scheduler.schedule_after(Duration::MAX, |_| {});
+        let state = scheduler.state();
+        let saved = state
+            .calls
+            .iter()
+            .find(|call| call.handle == never.to_raw());
+        assert_eq!(saved.map(|call| call.due_nanos), Some(u64::MAX));
+    }
+
+    #[test]
+    fn cancel_and_reschedule() {
+        let mut time = Time::default();
+        let mut scheduler = Scheduler::<u32>::new();
+        let cancelled = scheduler.schedule_after(Duration::from_millis(10), |n| *n += 1);
+        let moved = scheduler.schedule_after(Duration::from_millis(10), |n| *n += 10);
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.cancel(cancelled));
+        assert!(scheduler.reschedule(moved, Duration::from_millis(40)));
+        assert_eq!(scheduler.next_due(), Some(Duration::from_millis(40)));
+
+        let mut count = 0;
+        time.advance_frame(Duration::from_millis(20));
+        scheduler.update(&time);
+        scheduler.run_due(&mut count);
+        assert_eq!(count, 0);
+        assert!(scheduler.is_scheduled(moved));
+
+        time.advance_frame(Duration::from_millis(20));
+        scheduler.update(&time);
+        scheduler.run_due(&mut count);
+        assert_eq!(count, 10);
+        assert!(scheduler.is_empty());
+
+        for _ in 0..1_000 {
+            let handle = scheduler.schedule_after(Duration::from_secs(60), |n| *n += 100);
+            scheduler.reschedule(handle, Duration::from_secs(120));
+            scheduler.cancel(handle);
+        }
+        if let super::Queue::Heap(heap) = &scheduler.queue {
+            assert!(heap.len() <= 2);
+        }
+        scheduler.schedule_after(Duration::MAX, |_| {});
+        assert_eq!(scheduler.next_due(), Some(Duration::MAX));
+    }
+}
@@ -0,0 +1,219 @@
+//! Lock-free publication of the frequently read timing values to other threads.
+
+use std::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::{to_nanos, Time};
+
+/// The frequently read timing values of a `Time`, packed in plain integers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PackedTime {
+    /// Time elapsed since the last frame, in nanoseconds.
+    pub delta_nanos: u64,
+    /// Time elapsed since the last frame ignoring the time speed multiplier, in nanoseconds.
+    pub delta_real_nanos: u64,
+    /// Time elapsed since game start, taking the speed multiplier into account, in nanoseconds.
+    pub absolute_nanos: u64,
+    /// Time elapsed since game start, ignoring the speed multiplier, in nanoseconds.
+    pub absolute_real_nanos: u64,
+    /// The current frame number.
+    pub frame_number: u64,
+}
+
+impl PackedTime {
+    /// Gets the time difference between frames.
+    pub fn delta_time(&self) -> Duration {
+        Duration::from_nanos(self.delta_nanos)
+    }
+
+    /// Gets the time difference between frames ignoring the time speed multiplier.
+    pub fn delta_real_time(&self) -> Duration {
+        Duration::from_nanos(self.delta_real_nanos)
+    }
+
+    /// Gets the time since the start of the game, taking into account the speed multiplier.
+    pub fn absolute_time(&self) -> Duration {
+        Duration::from_nanos(self.absolute_nanos)
+    }
+
+    /// Gets the time since the start of the game, ignoring the speed multiplier.
+    pub fn absolute_real_time(&self) -> Duration {
+        Duration::from_nanos(self.absolute_real_nanos)
+    }
+}
+
+impl From<&Time> for PackedTime {
+    fn from(time: &Time) -> Self {
+        time.pack()
+    }
+}
+
+/// Shares the values of a `Time` between threads without locks.
+///
+/// The thread owning the clock calls `publish` once per frame, after `advance_frame`.
+/// Any number of threads can then `load` a consistent snapshot without blocking the owner.
+/// This is a sequence lock: only one thread may publish at a time.
+///
+/// Threads can also block until game time reached a point with `blocking_wait_game_time`.
+/// Publishing only takes a lock while such a thread is waiting.
+///
+/// The struct is aligned on a cache line so that readers polling it don't slow down
+/// unrelated data stored next to it.
+#[repr(align(64))]
+#[derive(Debug, Default)]
+pub struct SharedTime {
+    /// Odd while a publication is in progress.
+    sequence: AtomicU64,
+    delta_nanos: AtomicU64,
+    delta_real_nanos: AtomicU64,
+    absolute_nanos: AtomicU64,
+    absolute_real_nanos: AtomicU64,
+    frame_number: AtomicU64,
+    /// Number of threads blocked in `blocking_wait_until_game_time`.
+    waiters: AtomicUsize,
+    /// Wakes the blocked threads on each publication.
+    wakeup: (Mutex<()>, Condvar),
+}
+
+impl SharedTime {
+    /// Creates a shared time holding the values of a default `Time`.
+    pub fn new() -> Self {
+        SharedTime::default()
+    }
+
+    /// Publishes the current values of the clock to the readers.
+    pub fn publish(&self, time: &Time) {
+        self.store(PackedTime::from(time));
+    }
+
+    /// Publishes already packed values to the readers.
+    pub fn store(&self, packed: PackedTime) {
+        self.sequence.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.delta_nanos
+            .store(packed.delta_nanos, Ordering::Relaxed);
+        self.delta_real_nanos
+            .store(packed.delta_real_nanos, Ordering::Relaxed);
+        self.absolute_nanos
+            .store(packed.absolute_nanos, Ordering::Relaxed);
+        self.absolute_real_nanos
+            .store(packed.absolute_real_nanos, Ordering::Relaxed);
+        self.frame_number
+            .store(packed.frame_number, Ordering::Relaxed);
+        self.sequence.fetch_add(1, Ordering::Release);
+        // Pairs with the fence of waiting threads, so that either they see the new values or
+        // this sees them waiting.
+        fence(Ordering::SeqCst);
+        if self.waiters.load(Ordering::Relaxed) > 0 {
+            let _lock = self.wakeup.0.lock().unwrap_or_else(|e| e.into_inner());
+            self.wakeup.1.notify_all();
+        }
+    }
+
+    /// Blocks the calling thread until `duration` of scaled game time passed, as published by
+    /// the thread owning the clock, and returns the values published then. The wait is in game
+    /// time: it lasts longer in slow motion and doesn't end while the game is paused.
+    ///
+    /// Meant for threads running blocking script interpreters, never for the thread
+    /// publishing the values, which would wait forever.
+    pub fn blocking_wait_game_time(&self, duration: Duration) -> PackedTime {
+        let target = self.load().absolute_time() + duration;
+        self.blocking_wait_until_game_time(target)
+    }
+
+    /// Blocks the calling thread until the published scaled absolute time reaches `target`,
+    /// and returns the values published then. See `blocking_wait_game_time`.
+    pub fn blocking_wait_until_game_time(&self, target: Duration) -> PackedTime {
+        let target = to_nanos(target);
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let mut lock = self.wakeup.0.lock().unwrap_or_else(|e| e.into_inner());
+        let packed = loop {
+            let packed = self.load();
+            if packed.absolute_nanos >= target {
+                break packed;
+            }
+            lock = self.wakeup.1.wait(lock).unwrap_or_else(|e| e.into_inner());
+        };
+        drop(lock);
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+        packed
+    }
+
+    /// Reads the last published values. Retries while a publication is in progress.
+    pub fn load(&self) -> PackedTime {
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let packed = PackedTime {
+                delta_nanos: self.delta_nanos.load(Ordering::Relaxed),
+                delta_real_nanos: self.delta_real_nanos.load(Ordering::Relaxed),
+                absolute_nanos: self.absolute_nanos.load(Ordering::Relaxed),
+                absolute_real_nanos: self.absolute_real_nanos.load(Ordering::Relaxed),
+                frame_number: self.frame_number.load(Ordering::Relaxed),
+            };
+            fence(Ordering::Acquire);
+            if self.sequence.load(Ordering::Relaxed) == before {
+                return packed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn readers_see_consistent_snapshots() {
+        let shared = Arc::new(SharedTime::new());
+        let reader = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let mut last_frame = 0;
+                while last_frame < 10_000 {
+                    let packed = shared.load();
+                    assert_eq!(packed.absolute_real_nanos, packed.frame_number * 1_000);
+                    assert!(packed.frame_number >= last_frame);
+                    last_frame = packed.frame_number;
+                }
+            })
+        };
+
+        let mut time = Time::default();
+        for _ in 0..10_000 {
+            time.advance_frame(Duration::from_micros(1));
+            shared.publish(&time);
+        }
+        reader.join().unwrap();
+        assert_eq!(shared.load(), PackedTime::from(&time));
+    }
+
+    #[test]
+    fn script_waits_in_game_time() {
+        let shared = Arc::new(SharedTime::new());
+        let script = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.blocking_wait_game_time(Duration::from_secs(1)))
+        };
+
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        while !script.is_finished() {
+            time.advance_frame(Duration::from_millis(100));
+            shared.publish(&time);
+            thread::sleep(Duration::from_millis(1));
+        }
+        let woken = script.join().unwrap();
+        assert!(woken.absolute_time() >= Duration::from_secs(1));
+        assert!(woken.absolute_real_time() >= Duration::from_secs(2));
+    }
+}
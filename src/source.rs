@@ -0,0 +1,85 @@
+//! Where code measuring real time reads the current time from, so that it can be tested
+//! without sleeping.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::limiter::wait_until;
+use crate::LimiterStrategy;
+
+/// Where a `Watchdog`, a `FrameLimiter` or `Time::step_fixed_update_with_deadline_from` reads
+/// the current time from.
+pub trait ClockSource: fmt::Debug + Send + Sync {
+    /// Gets the current time.
+    fn now(&self) -> Instant;
+
+    /// Waits until `deadline` following `strategy`, for a `FrameLimiter`.
+    /// Sleeps or spins on the system clock by default.
+    fn wait_until(&self, deadline: Instant, strategy: LimiterStrategy) {
+        wait_until(deadline, strategy);
+    }
+}
+
+/// Reads the time from `Instant::now`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for deterministic tests of real time code.
+///
+/// Clones share the same time, so a test can keep one and give another to the code it
+/// tests. Waiting on it moves it to the deadline instantly.
+#[derive(Clone, Debug)]
+pub struct ManualClock(Arc<Mutex<Instant>>);
+
+impl ManualClock {
+    /// Creates a clock stopped at `now`.
+    pub fn new(now: Instant) -> Self {
+        ManualClock(Arc::new(Mutex::new(now)))
+    }
+
+    fn instant(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.instant() += by;
+    }
+}
+
+impl ClockSource for ManualClock {
+    fn now(&self) -> Instant {
+        *self.instant()
+    }
+
+    fn wait_until(&self, deadline: Instant, _strategy: LimiterStrategy) {
+        let mut now = self.instant();
+        *now = (*now).max(deadline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn manual_clock_moves_when_told() {
+        let start = Instant::now();
+        let clock = ManualClock::new(start);
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(5));
+        assert_eq!(clock.now(), start + Duration::from_millis(5));
+        clock.wait_until(start, LimiterStrategy::Spin);
+        assert_eq!(clock.now(), start + Duration::from_millis(5));
+        clock.wait_until(start + Duration::from_millis(8), LimiterStrategy::Spin);
+        assert_eq!(shared.now(), start + Duration::from_millis(8));
+    }
+}
@@ -0,0 +1,217 @@
+//! Versioned snapshots of the state of a clock, for saves and replays.
+//!
+//! Every layout the internal state ever had is kept as its own struct, and older versions
+//! migrate to the latest one, so states written by old versions of the crate can still be
+//! restored. The fields are plain integers and floats, easy to write with any format.
+
+use std::time::Duration;
+
+use crate::ScaleRatio;
+
+/// State of a clock as stored by versions where times were `Duration`s and the time scale
+/// an `f32`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimeStateV1 {
+    /// Time elapsed since the last frame.
+    pub delta_time: Duration,
+    /// Time elapsed since the last frame ignoring the time speed multiplier.
+    pub delta_real_time: Duration,
+    /// Fixed time step.
+    pub fixed_time: Duration,
+    /// The total number of frames that have been played.
+    pub frame_number: u64,
+    /// Time elapsed since game start, ignoring the speed multiplier.
+    pub absolute_real_time: Duration,
+    /// Time elapsed since game start, taking the speed multiplier into account.
+    pub absolute_time: Duration,
+    /// Time multiplier.
+    pub time_scale: f32,
+    /// Fixed timestep accumulator.
+    pub fixed_time_accumulator: Duration,
+}
+
+/// State of a clock as stored since times are integer nanoseconds and the time scale an
+/// exact ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TimeStateV2 {
+    /// Time elapsed since the last frame, in nanoseconds.
+    pub delta_nanos: u64,
+    /// Time elapsed since the last frame ignoring the time speed multiplier, in nanoseconds.
+    pub delta_real_nanos: u64,
+    /// Fixed time step, in nanoseconds.
+    pub fixed_nanos: u64,
+    /// The total number of frames that have been played.
+    pub frame_number: u64,
+    /// Time elapsed since game start, ignoring the speed multiplier, in nanoseconds.
+    pub absolute_real_nanos: u64,
+    /// Time elapsed since game start, taking the speed multiplier into account, in nanoseconds.
+    pub absolute_nanos: u64,
+    /// Numerator of the time multiplier set by the user, before scale layers.
+    pub time_scale_numerator: u64,
+    /// Denominator of the time multiplier set by the user, before scale layers.
+    pub time_scale_denominator: u64,
+    /// Fraction of a scaled nanosecond carried to the next frame,
+    /// in units of `1 / time_scale_denominator` nanoseconds.
+    pub scale_remainder: u64,
+    /// Fixed timestep accumulator, in nanoseconds.
+    pub fixed_accumulator_nanos: u64,
+    /// Fixed steps run since the clock was created.
+    pub fixed_step_count: u64,
+    /// Sum of the fixed time steps run since the clock was created, in nanoseconds.
+    pub last_fixed_step_nanos: u64,
+}
+
+/// The latest version of the state of a clock.
+pub type TimeStateLatest = TimeStateV2;
+
+impl From<TimeStateV1> for TimeStateV2 {
+    /// Fixed steps run before the migration are not known, and are estimated from the
+    /// real absolute time.
+    fn from(v1: TimeStateV1) -> Self {
+        let nanos = crate::to_nanos;
+        let ratio = ScaleRatio::from_f64(f64::from(v1.time_scale));
+        let fixed_nanos = nanos(v1.fixed_time);
+        let fixed_step_count = nanos(v1.absolute_real_time)
+            .saturating_sub(nanos(v1.fixed_time_accumulator))
+            .checked_div(fixed_nanos)
+            .unwrap_or(0);
+        TimeStateV2 {
+            delta_nanos: nanos(v1.delta_time),
+            delta_real_nanos: nanos(v1.delta_real_time),
+            fixed_nanos,
+            frame_number: v1.frame_number,
+            absolute_real_nanos: nanos(v1.absolute_real_time),
+            absolute_nanos: nanos(v1.absolute_time),
+            time_scale_numerator: ratio.numerator,
+            time_scale_denominator: ratio.denominator,
+            scale_remainder: 0,
+            fixed_accumulator_nanos: nanos(v1.fixed_time_accumulator),
+            fixed_step_count,
+            last_fixed_step_nanos: fixed_step_count.saturating_mul(fixed_nanos),
+        }
+    }
+}
+
+/// A state of a clock in any of the versions ever stored.
+///
+/// Store the version number next to the fields, and build the matching variant when
+/// reading them back.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeState {
+    /// See `TimeStateV1`.
+    V1(TimeStateV1),
+    /// See `TimeStateV2`.
+    V2(TimeStateV2),
+}
+
+impl TimeState {
+    /// Gets the version number of the state.
+    pub fn version(&self) -> u32 {
+        match self {
+            TimeState::V1(_) => 1,
+            TimeState::V2(_) => 2,
+        }
+    }
+
+    /// Migrates the state to the latest version.
+    pub fn migrate(self) -> TimeStateLatest {
+        match self {
+            TimeState::V1(v1) => TimeStateV2::from(v1),
+            TimeState::V2(v2) => v2,
+        }
+    }
+}
+
+impl From<TimeStateV1> for TimeState {
+    fn from(v1: TimeStateV1) -> Self {
+        TimeState::V1(v1)
+    }
+}
+
+impl From<TimeStateV2> for TimeState {
+    fn from(v2: TimeStateV2) -> Self {
+        TimeState::V2(v2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn save_and_restore() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale_ratio(2, 3);
+        time.set_scale_layer("slowmo", 0.5);
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {}
+
+        let state = time.state();
+        assert_eq!(
+            (state.time_scale_numerator, state.time_scale_denominator),
+            (2, 3)
+        );
+        let mut restored = Time::default();
+        restored.restore_state(TimeState::V2(state));
+        assert_eq!(restored.state(), state);
+        time.clear_scale_layer("slowmo");
+        assert_eq!(restored.tick_fingerprint(), time.tick_fingerprint());
+    }
+
+    #[test]
+    fn restore_earlier_state() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_frame_history_len(8);
+        time.advance_frame(Duration::from_millis(16));
+        let early = time.state();
+        for _ in 0..5 {
+            time.advance_frame(Duration::from_millis(16));
+        }
+        time.ramp_time_scale(0.5, Duration::from_secs(1), Easing::Linear);
+        let mut track = ScaleTrack::new();
+        track.add_key(Duration::from_secs(1), 0.5, Easing::Linear);
+        time.play_scale_track(track);
+        time.advance_frame(Duration::from_millis(16));
+
+        time.restore_state(early);
+        assert_eq!(
+            time.frames_between(Duration::from_secs(0), Duration::MAX),
+            2..2
+        );
+        time.advance_frame(Duration::from_millis(16));
+        assert_eq!(time.frame_number(), 2);
+        assert_eq!(time.time_scale(), 1.0);
+        assert_eq!(
+            time.frames_between(Duration::from_secs(0), Duration::MAX),
+            2..3
+        );
+
+        // A zero fixed time step, as migrated from a default V1 state, is ignored.
+        time.restore_state(TimeStateV1::default());
+        assert_eq!(time.fixed_time(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn migrate_v1() {
+        let v1 = TimeStateV1 {
+            delta_time: Duration::from_millis(32),
+            delta_real_time: Duration::from_millis(16),
+            fixed_time: Duration::from_millis(10),
+            frame_number: 10,
+            absolute_real_time: Duration::from_millis(160),
+            absolute_time: Duration::from_millis(320),
+            time_scale: 2.0,
+            fixed_time_accumulator: Duration::from_millis(0),
+        };
+        let state = TimeState::from(v1);
+        assert_eq!(state.version(), 1);
+        let mut time = Time::default();
+        time.restore_state(state);
+        assert_eq!(time.absolute_time(), Duration::from_millis(320));
+        assert_eq!(time.time_scale(), 2.0);
+        assert_eq!(time.fixed_step_count(), 16);
+    }
+}
@@ -0,0 +1,366 @@
+//! Rolling statistics over the last frames of a clock.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::Time;
+
+/// Timing values of one recorded frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameRecord {
+    /// Number of the frame.
+    pub frame_number: u64,
+    /// Time elapsed since the previous frame ignoring the time speed multiplier.
+    pub delta_real_time: Duration,
+    /// Time elapsed since the previous frame.
+    pub delta_time: Duration,
+    /// Number of fixed steps that ran during the frame.
+    pub fixed_steps: u32,
+}
+
+/// How many frames ran 0, 1, 2, or 3 and more fixed steps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FixedStepHistogram {
+    /// Frame counts, indexed by number of fixed steps. The last bucket counts 3 steps and more.
+    pub buckets: [usize; 4],
+}
+
+impl FixedStepHistogram {
+    /// Gets the number of frames that ran `steps` fixed steps.
+    /// Every value of 3 and above reads the "3 and more" bucket.
+    pub fn frames_with(&self, steps: u32) -> usize {
+        self.buckets[(steps as usize).min(3)]
+    }
+
+    /// Gets the total number of frames counted.
+    pub fn total(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+}
+
+/// Keeps the timing values of the last frames of a clock.
+///
+/// Call `record` once per frame, after the fixed updates of the frame ran.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameStats {
+    /// Maximum number of frames kept.
+    capacity: usize,
+    /// Recorded frames, oldest first.
+    frames: VecDeque<FrameRecord>,
+}
+
+impl FrameStats {
+    /// Creates statistics keeping the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        FrameStats {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Gets the maximum number of frames kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Records the current frame of the clock, dropping the oldest frame if full.
+    pub fn record(&mut self, time: &Time) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(FrameRecord {
+            frame_number: time.frame_number(),
+            delta_real_time: time.delta_real_time(),
+            delta_time: time.delta_time(),
+            fixed_steps: time.fixed_steps_this_frame(),
+        });
+    }
+
+    /// Gets the recorded frames, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameRecord> {
+        self.frames.iter()
+    }
+
+    /// Gets the most recently recorded frame.
+    pub fn last(&self) -> Option<&FrameRecord> {
+        self.frames.back()
+    }
+
+    /// Gets how many of the recorded frames ran 0, 1, 2, or 3 and more fixed steps.
+    pub fn fixed_step_histogram(&self) -> FixedStepHistogram {
+        let mut histogram = FixedStepHistogram::default();
+        for frame in &self.frames {
+            histogram.buckets[(frame.fixed_steps as usize).min(3)] += 1;
+        }
+        histogram
+    }
+
+    /// Forgets every recorded frame.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// Timing totals of a whole session, for crash reports and analytics.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionSummary {
+    /// Number of frames recorded.
+    pub frames: u64,
+    /// Total real time of the recorded frames.
+    pub real_time: Duration,
+    /// Total scaled time of the recorded frames.
+    pub scaled_time: Duration,
+    /// Frames per second over the whole session.
+    pub average_fps: f64,
+    /// Frames per second of the longest frame.
+    pub min_fps: f64,
+    /// Frames per second of the shortest frame.
+    pub max_fps: f64,
+    /// Number of frames longer than the hitch threshold of the clock.
+    pub hitches: u64,
+    /// Number of fixed steps run during the recorded frames.
+    pub fixed_steps: u64,
+    /// Real delta of the longest frame.
+    pub longest_frame: Duration,
+    /// Number of the longest frame.
+    pub longest_frame_number: u64,
+}
+
+/// Writes one `name value` pair per line, times in nanoseconds.
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "frames {}", self.frames)?;
+        writeln!(f, "real_time {}", self.real_time.as_nanos())?;
+        writeln!(f, "scaled_time {}", self.scaled_time.as_nanos())?;
+        writeln!(f, "average_fps {:.2}", self.average_fps)?;
+        writeln!(f, "min_fps {:.2}", self.min_fps)?;
+        writeln!(f, "max_fps {:.2}", self.max_fps)?;
+        writeln!(f, "hitches {}", self.hitches)?;
+        writeln!(f, "fixed_steps {}", self.fixed_steps)?;
+        writeln!(f, "longest_frame {}", self.longest_frame.as_nanos())?;
+        writeln!(f, "longest_frame_number {}", self.longest_frame_number)
+    }
+}
+
+/// Accumulates the timing of every frame of a session, in constant memory.
+///
+/// Call `record` once per frame, after the fixed updates of the frame ran, and `summary`
+/// whenever a report is needed, for example at shutdown or from a panic hook.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionStats {
+    summary: SessionSummary,
+    /// Real delta of the shortest frame that took any time.
+    shortest_frame: Option<Duration>,
+}
+
+impl SessionStats {
+    /// Creates statistics with no frames recorded.
+    pub fn new() -> Self {
+        SessionStats::default()
+    }
+
+    /// Records the current frame of the clock.
+    pub fn record(&mut self, time: &Time) {
+        let delta = time.delta_real_time();
+        let summary = &mut self.summary;
+        summary.frames += 1;
+        summary.real_time += delta;
+        summary.scaled_time += time.delta_time();
+        summary.fixed_steps += u64::from(time.fixed_steps_this_frame());
+        if time.is_hitch() {
+            summary.hitches += 1;
+        }
+        if delta > summary.longest_frame || summary.frames == 1 {
+            summary.longest_frame = delta;
+            summary.longest_frame_number = time.frame_number();
+        }
+        if delta > Duration::from_secs(0)
+            && self.shortest_frame.is_none_or(|shortest| delta < shortest)
+        {
+            self.shortest_frame = Some(delta);
+        }
+    }
+
+    /// Gets the totals of the frames recorded so far.
+    pub fn summary(&self) -> SessionSummary {
+        let fps = |duration: Duration| {
+            if duration > Duration::from_secs(0) {
+                1.0 / duration.as_secs_f64()
+            } else {
+                0.0
+            }
+        };
+        SessionSummary {
+            average_fps: fps(self.summary.real_time) * self.summary.frames as f64,
+            min_fps: fps(self.summary.longest_frame),
+            max_fps: self.shortest_frame.map_or(0.0, fps),
+            ..self.summary
+        }
+    }
+}
+
+/// Named phases of a frame, like update and render, and how long they took in the current
+/// and previous frames. A quick CPU-side frame breakdown, without a profiler.
+///
+/// Call `phase` when each phase starts, and `end_frame` when the frame is over. A phase lasts
+/// until the next one starts. Phases started several times in a frame add up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FramePhases {
+    /// Phases that ended in the current frame, in order.
+    current: Vec<(&'static str, Duration)>,
+    /// Phase in progress and when it started.
+    open: Option<(&'static str, Instant)>,
+    /// Phases of the previous frame, in order.
+    previous: Vec<(&'static str, Duration)>,
+}
+
+impl FramePhases {
+    /// Creates an empty breakdown.
+    pub fn new() -> Self {
+        FramePhases::default()
+    }
+
+    /// Starts the named phase now, ending the phase in progress.
+    pub fn phase(&mut self, name: &'static str) {
+        self.phase_at(name, Instant::now());
+    }
+
+    /// Starts the named phase at the given instant, ending the phase in progress.
+    pub fn phase_at(&mut self, name: &'static str, at: Instant) {
+        self.close(at);
+        self.open = Some((name, at));
+    }
+
+    /// Ends the frame now. See `end_frame_at`.
+    pub fn end_frame(&mut self) {
+        self.end_frame_at(Instant::now());
+    }
+
+    /// Ends the phase in progress and the frame at the given instant. The phases of the frame
+    /// become the previous frame.
+    pub fn end_frame_at(&mut self, at: Instant) {
+        self.close(at);
+        std::mem::swap(&mut self.current, &mut self.previous);
+        self.current.clear();
+    }
+
+    fn close(&mut self, at: Instant) {
+        if let Some((name, start)) = self.open.take() {
+            self.current
+                .push((name, at.saturating_duration_since(start)));
+        }
+    }
+
+    /// Gets the phases that ended in the current frame, in order.
+    pub fn current(&self) -> &[(&'static str, Duration)] {
+        &self.current
+    }
+
+    /// Gets the phases of the previous frame, in order.
+    pub fn previous(&self) -> &[(&'static str, Duration)] {
+        &self.previous
+    }
+
+    /// Gets the time taken by the named phase in the current frame, so far.
+    pub fn current_duration(&self, name: &str) -> Option<Duration> {
+        total(&self.current, name)
+    }
+
+    /// Gets the time taken by the named phase in the previous frame.
+    pub fn previous_duration(&self, name: &str) -> Option<Duration> {
+        total(&self.previous, name)
+    }
+}
+
+/// Sums the durations of the named phase, or None if it didn't run.
+fn total(phases: &[(&'static str, Duration)], name: &str) -> Option<Duration> {
+    phases
+        .iter()
+        .filter(|(phase, _)| *phase == name)
+        .map(|(_, duration)| *duration)
+        .reduce(|a, b| a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn histogram_of_fixed_steps() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        let mut stats = FrameStats::new(4);
+
+        for delta in &[5, 5, 10, 25, 40, 10] {
+            time.advance_frame(Duration::from_millis(*delta));
+            while time.step_fixed_update() {}
+            stats.record(&time);
+        }
+
+        // Only the last 4 frames are kept: 1, 2, 4 and 1 steps.
+        let histogram = stats.fixed_step_histogram();
+        assert_eq!(histogram.buckets, [0, 2, 1, 1]);
+        assert_eq!(histogram.frames_with(7), 1);
+        assert_eq!(histogram.total(), 4);
+        assert_eq!(stats.last().unwrap().frame_number, 6);
+    }
+
+    #[test]
+    fn session_summary() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_time_scale(2.0);
+        let mut stats = SessionStats::new();
+        for delta in &[10, 20, 250, 20] {
+            time.advance_frame(Duration::from_millis(*delta));
+            while time.step_fixed_update() {}
+            stats.record(&time);
+        }
+
+        let summary = stats.summary();
+        assert_eq!(summary.frames, 4);
+        assert_eq!(summary.real_time, Duration::from_millis(300));
+        assert_eq!(summary.scaled_time, Duration::from_millis(600));
+        assert_eq!(summary.hitches, 1);
+        assert_eq!(summary.fixed_steps, 30);
+        assert_eq!(summary.longest_frame_number, 3);
+        assert!((summary.average_fps - 13.333).abs() < 0.001);
+        assert!((summary.min_fps - 4.0).abs() < 1e-9);
+        assert!((summary.max_fps - 100.0).abs() < 1e-9);
+        assert_eq!(summary.to_string().lines().nth(6), Some("hitches 1"));
+    }
+
+    #[test]
+    fn frame_phase_durations() {
+        let start = std::time::Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut phases = FramePhases::new();
+        phases.phase_at("update", ms(0));
+        phases.phase_at("render", ms(4));
+        phases.phase_at("update", ms(10));
+        assert_eq!(
+            phases.current_duration("update"),
+            Some(Duration::from_millis(4))
+        );
+        phases.end_frame_at(ms(11));
+        assert!(phases.current().is_empty());
+        assert_eq!(
+            phases.previous_duration("update"),
+            Some(Duration::from_millis(5))
+        );
+        assert_eq!(
+            phases.previous_duration("render"),
+            Some(Duration::from_millis(6))
+        );
+        assert_eq!(phases.previous_duration("audio"), None);
+
+        phases.phase_at("update", ms(16));
+        phases.end_frame_at(ms(18));
+        assert_eq!(phases.previous(), &[("update", Duration::from_millis(2))]);
+    }
+}
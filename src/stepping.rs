@@ -0,0 +1,188 @@
+//! Pluggable rules turning the fixed step accumulator into fixed steps.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::{to_nanos, CatchUp};
+
+/// Timing of the frame just advanced, given to a `SteppingPolicy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StepFrame {
+    /// Real delta of the frame.
+    pub delta_real_time: Duration,
+    /// Real delta of the previous frame.
+    pub previous_delta_real_time: Duration,
+    /// Fixed time step.
+    pub fixed_time: Duration,
+    /// Time waiting in the accumulator, including the delta of the frame.
+    pub accumulator: Duration,
+}
+
+/// What a `SteppingPolicy` decided for a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StepPlan {
+    /// Maximum number of fixed steps to run this frame. Time left in the accumulator carries
+    /// over to the next frames.
+    pub max_steps: u32,
+    /// Time removed from the accumulator before any step runs, and never simulated.
+    pub discard: Duration,
+    /// True to end the frame with one shorter step consuming the rest of the accumulator,
+    /// once the full steps ran. The step counts towards `max_steps`, and `Time::delta_time`
+    /// gives its length in the fixed update phase.
+    pub partial_step: bool,
+}
+
+/// Decides how the fixed step accumulator turns into fixed steps, each frame.
+///
+/// Set one on a clock with `Time::set_stepping_policy` to experiment with stepping schemes.
+/// `CatchUp` implements it, and is used when no policy is set.
+pub trait SteppingPolicy: fmt::Debug + Send {
+    /// Plans the fixed steps of the frame just advanced.
+    /// Also called when the fixed time step or the policy of the clock changes.
+    fn plan(&mut self, frame: &StepFrame) -> StepPlan;
+}
+
+impl CatchUp {
+    /// Gets the fixed steps allowed for a frame, from its real deltas and the fixed time step
+    /// in nanoseconds. Called by `Time` every frame, so this avoids the `Duration` conversions
+    /// of `StepFrame`.
+    pub(crate) fn max_steps(self, delta_real: u64, previous_delta_real: u64, fixed: u64) -> u32 {
+        match self {
+            CatchUp::Immediate => u32::MAX,
+            CatchUp::Smooth { max_extra_steps } => {
+                let regular = if previous_delta_real == 0 {
+                    delta_real
+                } else {
+                    delta_real.min(previous_delta_real)
+                };
+                let nominal = regular.div_ceil(fixed.max(1));
+                let nominal = nominal.min(u64::from(u32::MAX)) as u32;
+                nominal.saturating_add(max_extra_steps)
+            }
+        }
+    }
+}
+
+impl SteppingPolicy for CatchUp {
+    fn plan(&mut self, frame: &StepFrame) -> StepPlan {
+        StepPlan {
+            max_steps: self.max_steps(
+                to_nanos(frame.delta_real_time),
+                to_nanos(frame.previous_delta_real_time),
+                to_nanos(frame.fixed_time),
+            ),
+            ..StepPlan::default()
+        }
+    }
+}
+
+/// Runs at most `max_steps` fixed steps per frame and drops the rest of the backlog, so
+/// the simulation falls behind real time instead of spiraling when it can't keep up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClampedStepping {
+    /// Maximum number of fixed steps per frame.
+    pub max_steps: u32,
+}
+
+impl SteppingPolicy for ClampedStepping {
+    fn plan(&mut self, frame: &StepFrame) -> StepPlan {
+        let fixed = frame.fixed_time.as_nanos().max(1);
+        let accumulator = frame.accumulator.as_nanos();
+        let kept = u128::from(self.max_steps) * fixed + accumulator % fixed;
+        StepPlan {
+            max_steps: self.max_steps,
+            discard: Duration::from_nanos(accumulator.saturating_sub(kept) as u64),
+            partial_step: false,
+        }
+    }
+}
+
+/// Semi-fixed stepping: runs full fixed steps, then one shorter step with the rest of the
+/// accumulator, so the simulation always catches up with real time and never interpolates.
+/// At most `max_steps` steps run per frame, the backlog beyond them being dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SemiFixedStepping {
+    /// Maximum number of steps per frame, including the shorter one.
+    pub max_steps: u32,
+}
+
+impl SteppingPolicy for SemiFixedStepping {
+    fn plan(&mut self, frame: &StepFrame) -> StepPlan {
+        let kept = u128::from(self.max_steps) * frame.fixed_time.as_nanos();
+        let discard = frame.accumulator.as_nanos().saturating_sub(kept);
+        StepPlan {
+            max_steps: self.max_steps,
+            discard: Duration::from_nanos(discard as u64),
+            partial_step: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Runs a single fixed step per frame, however late the simulation is, and counts the
+    /// frames it planned.
+    #[derive(Debug)]
+    struct OneStep(Arc<AtomicU32>);
+
+    impl SteppingPolicy for OneStep {
+        fn plan(&mut self, _frame: &StepFrame) -> StepPlan {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            StepPlan {
+                max_steps: 1,
+                ..StepPlan::default()
+            }
+        }
+    }
+
+    fn steps(time: &mut Time, delta: u64) -> u32 {
+        time.advance_frame(Duration::from_millis(delta));
+        let mut steps = 0;
+        while time.step_fixed_update() {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn custom_policies() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_stepping_policy(ClampedStepping { max_steps: 2 });
+        assert_eq!(steps(&mut time, 105), 2);
+        assert_eq!(time.fixed_step_alpha(), 0.5);
+
+        let planned = Arc::new(AtomicU32::new(0));
+        time.set_stepping_policy(OneStep(Arc::clone(&planned)));
+        assert_eq!(steps(&mut time, 30), 1);
+        assert_eq!(steps(&mut time, 0), 1);
+        assert_eq!(planned.load(Ordering::SeqCst), 3);
+        time.clear_stepping_policy();
+        assert!(!time.has_stepping_policy());
+        assert_eq!(steps(&mut time, 30), 4);
+    }
+
+    #[test]
+    fn semi_fixed_steps() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_stepping_policy(SemiFixedStepping { max_steps: 4 });
+        time.advance_frame(Duration::from_millis(25));
+        let mut lengths = vec![];
+        while let Some(time) = time.enter_fixed_update() {
+            lengths.push(time.delta_time().as_millis());
+        }
+        assert_eq!(lengths, vec![10, 10, 5]);
+        assert_eq!(time.fixed_step_alpha(), 0.0);
+        assert_eq!(time.last_fixed_step_time(), Duration::from_millis(25));
+
+        // 40ms is kept out of the 100ms backlog.
+        assert_eq!(steps(&mut time, 100), 4);
+        assert_eq!(time.last_fixed_step_time(), Duration::from_millis(65));
+    }
+}
@@ -0,0 +1,76 @@
+//! Splitting fixed steps into shorter physics steps.
+
+use std::time::Duration;
+
+use crate::{to_nanos, Time};
+
+/// Splits each fixed step into equal sub-steps no longer than a maximum, for stiff
+/// simulations (springs, ropes, fast bodies) that need finer integration than the fixed rate
+/// of the game, without changing the tick cadence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubStepper {
+    /// Longest allowed sub-step, in nanoseconds.
+    max_step: u64,
+}
+
+impl SubStepper {
+    /// Creates a sub-stepper making sub-steps of at most `max_step`.
+    ///
+    /// ## Panics
+    /// This will panic if max_step is zero.
+    pub fn new(max_step: Duration) -> Self {
+        assert!(max_step > Duration::from_secs(0));
+        SubStepper {
+            max_step: to_nanos(max_step),
+        }
+    }
+
+    /// Gets the longest allowed sub-step.
+    pub fn max_step(&self) -> Duration {
+        Duration::from_nanos(self.max_step)
+    }
+
+    /// Gets the number of sub-steps a fixed step of `fixed_time` is split into.
+    pub fn count(&self, fixed_time: Duration) -> u32 {
+        let count = to_nanos(fixed_time).div_ceil(self.max_step).max(1);
+        count.min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Calls `sub_step` with the length and index of every sub-step of a fixed step of the
+    /// clock. Call it once per fixed update. The sub-steps are equal to the nanosecond, the
+    /// last one absorbing the rounding, so they always add up to the fixed time step.
+    pub fn run(&self, time: &Time, mut sub_step: impl FnMut(Duration, u32)) {
+        let fixed = to_nanos(time.fixed_time());
+        let count = self.count(time.fixed_time());
+        let length = fixed / u64::from(count);
+        for index in 0..count {
+            let length = if index + 1 == count {
+                fixed - length * u64::from(count - 1)
+            } else {
+                length
+            };
+            sub_step(Duration::from_nanos(length), index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn sub_steps_add_up_to_fixed_step() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_nanos(16_666_666));
+        let stepper = SubStepper::new(Duration::from_millis(6));
+        assert_eq!(stepper.count(time.fixed_time()), 3);
+        assert_eq!(stepper.count(Duration::from_millis(6)), 1);
+
+        let mut steps = vec![];
+        stepper.run(&time, |length, index| {
+            steps.push((index, length.as_nanos()))
+        });
+        assert_eq!(steps, vec![(0, 5_555_555), (1, 5_555_555), (2, 5_555_556)]);
+    }
+}
@@ -0,0 +1,378 @@
+//! Helpers for property testing time dependent code, enabled with the `test-strategies`
+//! feature.
+//!
+//! The generators are seeded, so a failing case can be replayed from its seed. Feed the seed
+//! from any property testing framework (proptest, quickcheck) or from a plain loop.
+//! `SoakRunner` runs a game loop for hours of simulated time, to catch slow drifts.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::rng::SplitMix64;
+use crate::{CatchUp, FixedStepHistogram, SessionStats, SessionSummary, Time};
+
+/// An invariant of `Time` that does not hold. See `check_invariants`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InvariantViolation {
+    /// The time added by the last frame is longer than the absolute time it is part of.
+    ///
+    /// Reported deltas raised to the minimum delta are not checked, as they are longer than the
+    /// time that passed.
+    DeltaAboveAbsolute,
+    /// Time passed, but no frame was advanced.
+    TimeWithoutFrames,
+    /// The fixed step accumulator holds more time than ever passed.
+    AccumulatorAboveAbsolute,
+    /// More fixed steps ran this frame than the catch-up policy allows.
+    FixedStepBudgetExceeded,
+    /// The time scale is negative, infinite or NaN.
+    InvalidTimeScale,
+    /// The carried fraction of a scaled nanosecond is a whole nanosecond or more.
+    ScaleRemainderOverflow,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            InvariantViolation::DeltaAboveAbsolute => "delta is longer than absolute time",
+            InvariantViolation::TimeWithoutFrames => "time passed without any frame",
+            InvariantViolation::AccumulatorAboveAbsolute => {
+                "accumulator holds more than the real absolute time"
+            }
+            InvariantViolation::FixedStepBudgetExceeded => {
+                "more fixed steps ran than the catch-up policy allows"
+            }
+            InvariantViolation::InvalidTimeScale => "time scale is negative, infinite or NaN",
+            InvariantViolation::ScaleRemainderOverflow => "carried scale remainder overflowed",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Checks the invariants every clock upholds, whatever sequence of calls led to its state.
+pub fn check_invariants(time: &Time) -> Result<(), InvariantViolation> {
+    if time.frame_time > time.absolute_time || time.frame_real_time > time.absolute_real_time {
+        return Err(InvariantViolation::DeltaAboveAbsolute);
+    }
+    if time.frame_number == 0 && (time.absolute_time > 0 || time.absolute_real_time > 0) {
+        return Err(InvariantViolation::TimeWithoutFrames);
+    }
+    if time.fixed_time_accumulator > time.absolute_real_time {
+        return Err(InvariantViolation::AccumulatorAboveAbsolute);
+    }
+    if time.fixed_steps_this_frame > time.fixed_step_budget {
+        return Err(InvariantViolation::FixedStepBudgetExceeded);
+    }
+    if !(time.time_scale >= 0.0 && time.time_scale.is_finite()) {
+        return Err(InvariantViolation::InvalidTimeScale);
+    }
+    if time.scale_remainder >= time.scale_ratio.denominator {
+        return Err(InvariantViolation::ScaleRemainderOverflow);
+    }
+    Ok(())
+}
+
+/// Generates `count` realistic frame deltas: mostly around 60 frames per second with some
+/// jitter, with occasional hitches and very short frames.
+pub fn frame_deltas(seed: u64, count: usize) -> Vec<Duration> {
+    let mut rng = SplitMix64::new(seed);
+    (0..count).map(|_| realistic_delta(&mut rng)).collect()
+}
+
+fn realistic_delta(rng: &mut SplitMix64) -> Duration {
+    let roll = rng.next_f64();
+    let millis = if roll < 0.02 {
+        // Hitch: loading, GC pause, window drag.
+        50.0 + rng.next_f64() * 450.0
+    } else if roll < 0.05 {
+        // Frames that barely took any time.
+        rng.next_f64() * 0.5
+    } else {
+        16.666 + (rng.next_f64() - 0.5) * 4.0
+    };
+    Duration::from_secs_f64(millis / 1_000.0)
+}
+
+/// Hardware-like sequences of frame deltas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeltaPattern {
+    /// Every frame lasts the same time, like a game locked to 60Hz vsync.
+    Fixed(Duration),
+    /// Frames last `mean`, randomly shortened or lengthened by up to `jitter`.
+    Jittered {
+        /// Average frame duration.
+        mean: Duration,
+        /// Largest difference from the average.
+        jitter: Duration,
+    },
+    /// Frames last `base`, except every `period`th frame, which lasts `spike`, like a
+    /// periodic autosave or garbage collection.
+    PeriodicSpikes {
+        /// Duration of regular frames.
+        base: Duration,
+        /// Duration of spike frames.
+        spike: Duration,
+        /// Number of frames from one spike to the next.
+        period: u32,
+    },
+    /// Frames randomly last either `fast` or `slow`, like a game hovering around the
+    /// budget of a 60Hz display and dropping to 30Hz frames.
+    Bimodal {
+        /// Duration of fast frames.
+        fast: Duration,
+        /// Duration of slow frames.
+        slow: Duration,
+        /// Fraction of the frames that are slow, from 0 to 1.
+        slow_fraction: f32,
+    },
+}
+
+impl DeltaPattern {
+    /// Generates the first `count` frame deltas of the pattern, randomized from `seed`.
+    ///
+    /// ## Panics
+    /// This will panic if the period of spikes is 0.
+    pub fn generate(self, seed: u64, count: usize) -> Vec<Duration> {
+        let mut rng = SplitMix64::new(seed);
+        (0..count as u64)
+            .map(|frame| self.delta(frame, &mut rng))
+            .collect()
+    }
+
+    /// Gets the delta of the frame at index `frame`.
+    fn delta(self, frame: u64, rng: &mut SplitMix64) -> Duration {
+        match self {
+            DeltaPattern::Fixed(delta) => delta,
+            DeltaPattern::Jittered { mean, jitter } => {
+                let offset = (rng.next_f64() * 2.0 - 1.0) * jitter.as_secs_f64();
+                Duration::from_secs_f64((mean.as_secs_f64() + offset).max(0.0))
+            }
+            DeltaPattern::PeriodicSpikes {
+                base,
+                spike,
+                period,
+            } => {
+                assert!(period > 0);
+                if (frame + 1) % u64::from(period) == 0 {
+                    spike
+                } else {
+                    base
+                }
+            }
+            DeltaPattern::Bimodal {
+                fast,
+                slow,
+                slow_fraction,
+            } => {
+                if rng.next_f64() < f64::from(slow_fraction) {
+                    slow
+                } else {
+                    fast
+                }
+            }
+        }
+    }
+}
+
+/// Frame deltas fed to the clock by a `SoakRunner`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SoakPattern {
+    /// Exactly 60 frames per second.
+    #[default]
+    Steady,
+    /// Around 60 frames per second, each frame 2ms shorter or longer at most.
+    Noisy,
+    /// Like `frame_deltas`: noisy, with occasional hitches and very short frames.
+    Spiky,
+    /// Any other pattern.
+    Custom(DeltaPattern),
+}
+
+impl SoakPattern {
+    fn delta(self, frame: u64, rng: &mut SplitMix64) -> Duration {
+        match self {
+            SoakPattern::Steady => Duration::from_secs(1) / 60,
+            SoakPattern::Noisy => {
+                Duration::from_secs_f64((16.666 + (rng.next_f64() - 0.5) * 4.0) / 1_000.0)
+            }
+            SoakPattern::Spiky => realistic_delta(rng),
+            SoakPattern::Custom(pattern) => pattern.delta(frame, rng),
+        }
+    }
+}
+
+/// Statistics of a soak run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoakReport {
+    /// Totals of every frame of the run.
+    pub summary: SessionSummary,
+    /// How many frames of the run ran 0, 1, 2, or 3 and more fixed steps.
+    pub fixed_step_histogram: FixedStepHistogram,
+}
+
+/// Drives a game loop for a long stretch of simulated time with synthetic frame deltas, as
+/// a regression test of the timing behavior of a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoakRunner {
+    pattern: SoakPattern,
+    /// Real time to simulate.
+    duration: Duration,
+    seed: u64,
+}
+
+impl SoakRunner {
+    /// Creates a runner simulating `duration` of real time with frame deltas following
+    /// `pattern`, randomized from `seed`.
+    pub fn new(pattern: SoakPattern, duration: Duration, seed: u64) -> Self {
+        SoakRunner {
+            pattern,
+            duration,
+            seed,
+        }
+    }
+
+    /// Advances `time` frame by frame until the duration was simulated, calling `frame`
+    /// after each advance. `frame` runs the fixed updates and anything else the game does
+    /// in a frame.
+    pub fn run(&self, time: &mut Time, mut frame: impl FnMut(&mut Time)) -> SoakReport {
+        let mut rng = SplitMix64::new(self.seed);
+        let mut stats = SessionStats::new();
+        let mut histogram = FixedStepHistogram::default();
+        let mut simulated = Duration::from_secs(0);
+        let mut frames = 0;
+        while simulated < self.duration {
+            let delta = self.pattern.delta(frames, &mut rng);
+            frames += 1;
+            simulated += delta;
+            time.advance_frame(delta);
+            frame(time);
+            stats.record(time);
+            histogram.buckets[(time.fixed_steps_this_frame() as usize).min(3)] += 1;
+        }
+        SoakReport {
+            summary: stats.summary(),
+            fixed_step_histogram: histogram,
+        }
+    }
+}
+
+/// Generates a clock in a valid, realistic state: random configuration, advanced for a
+/// random number of frames with fixed updates run.
+pub fn arbitrary_time(seed: u64) -> Time {
+    let mut rng = SplitMix64::new(seed);
+    let mut time = Time::default();
+    let fixed_steps = [1_000_000_000 / 30, 1_000_000_000 / 60, 1_000_000_000 / 120];
+    time.set_fixed_time(Duration::from_nanos(
+        fixed_steps[(rng.next_u64() % 3) as usize],
+    ));
+    time.set_time_scale_f64([0.0, 0.5, 1.0, 2.0][(rng.next_u64() % 4) as usize]);
+    if rng.next_f64() < 0.5 {
+        time.set_catch_up(CatchUp::Smooth {
+            max_extra_steps: (rng.next_u64() % 4) as u32,
+        });
+    }
+    let frames = (rng.next_u64() % 500) as usize;
+    for delta in frame_deltas(rng.next_u64(), frames) {
+        time.advance_frame(delta);
+        while time.step_fixed_update() {}
+    }
+    time
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::*;
+
+    #[test]
+    fn arbitrary_times_uphold_invariants() {
+        for seed in 0..50 {
+            let time = arbitrary_time(seed);
+            assert_eq!(check_invariants(&time), Ok(()), "seed {}", seed);
+        }
+        assert_eq!(frame_deltas(7, 100), frame_deltas(7, 100));
+    }
+
+    #[test]
+    fn soak_steady_and_spiky() {
+        let run = |pattern| {
+            let mut time = Time::default();
+            time.set_fixed_time(Duration::from_secs(1) / 60);
+            let ten_minutes = Duration::from_secs(1) / 60 * 36_000;
+            SoakRunner::new(pattern, ten_minutes, 1)
+                .run(&mut time, |time| while time.step_fixed_update() {})
+        };
+        let steady = run(SoakPattern::Steady);
+        assert_eq!(steady.summary.frames, 36_000);
+        assert_eq!(steady.summary.hitches, 0);
+        assert_eq!(steady.fixed_step_histogram.frames_with(1), 36_000);
+
+        let spiky = run(SoakPattern::Spiky);
+        assert!(spiky.summary.hitches > 0);
+        assert!(spiky.fixed_step_histogram.frames_with(3) > 0);
+        assert_eq!(spiky, run(SoakPattern::Spiky));
+    }
+
+    #[test]
+    fn delta_patterns() {
+        let ms = Duration::from_millis;
+        let spikes = DeltaPattern::PeriodicSpikes {
+            base: ms(16),
+            spike: ms(100),
+            period: 3,
+        };
+        assert_eq!(
+            spikes.generate(0, 6),
+            vec![ms(16), ms(16), ms(100), ms(16), ms(16), ms(100)]
+        );
+        let bimodal = DeltaPattern::Bimodal {
+            fast: ms(16),
+            slow: ms(33),
+            slow_fraction: 0.25,
+        };
+        let slow = bimodal
+            .generate(3, 1_000)
+            .iter()
+            .filter(|d| **d == ms(33))
+            .count();
+        assert!((200..300).contains(&slow));
+        let jittered = DeltaPattern::Jittered {
+            mean: ms(16),
+            jitter: ms(2),
+        };
+        assert!(jittered
+            .generate(3, 1_000)
+            .iter()
+            .all(|d| (ms(14)..=ms(18)).contains(d)));
+
+        let mut time = Time::default();
+        let report =
+            SoakRunner::new(SoakPattern::Custom(spikes), ms(1_320), 0).run(&mut time, |_| {});
+        assert_eq!(report.summary.frames, 30);
+        assert_eq!(report.summary.hitches, 0);
+        assert_eq!(report.summary.longest_frame_number, 3);
+    }
+
+    #[test]
+    fn min_delta_upholds_invariants() {
+        let mut time = Time::default();
+        time.set_min_delta(Duration::from_millis(5));
+        time.advance_frame(Duration::from_millis(1));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(5));
+        assert_eq!(time.absolute_real_time(), Duration::from_millis(1));
+        assert_eq!(check_invariants(&time), Ok(()));
+    }
+
+    #[test]
+    fn detects_violation() {
+        let time = Time {
+            fixed_time_accumulator: 1,
+            ..Time::default()
+        };
+        assert_eq!(
+            check_invariants(&time),
+            Err(InvariantViolation::AccumulatorAboveAbsolute)
+        );
+    }
+}
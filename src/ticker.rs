@@ -0,0 +1,108 @@
+//! Random events happening at a given average rate of game time.
+
+use std::time::Duration;
+
+use crate::rng::SplitMix64;
+use crate::{to_nanos, Time};
+
+/// Fires random events following a Poisson process: events are independent and happen on
+/// average once per mean interval of game time. Suited to ambient sounds and random
+/// encounters.
+///
+/// The time until the next event is carried between frames, so the rate is the same at every
+/// frame rate and time scale, and a long frame fires every event it covered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomTicker {
+    /// Average time between events, in nanoseconds.
+    mean_interval: u64,
+    /// Game time left until the next event, in nanoseconds.
+    until_next: u64,
+    rng: SplitMix64,
+}
+
+impl RandomTicker {
+    /// Creates a randomly seeded ticker firing on average once per `mean_interval`.
+    ///
+    /// ## Panics
+    /// This will panic if mean_interval is zero.
+    pub fn with_mean_interval(mean_interval: Duration) -> Self {
+        RandomTicker::with_rng(mean_interval, SplitMix64::from_entropy())
+    }
+
+    /// Creates a ticker firing on average once per `mean_interval`, whose events are derived
+    /// from `seed`, to keep replays deterministic.
+    ///
+    /// ## Panics
+    /// This will panic if mean_interval is zero.
+    pub fn with_mean_interval_and_seed(mean_interval: Duration, seed: u64) -> Self {
+        RandomTicker::with_rng(mean_interval, SplitMix64::new(seed))
+    }
+
+    fn with_rng(mean_interval: Duration, rng: SplitMix64) -> Self {
+        assert!(mean_interval > Duration::from_secs(0));
+        let mut ticker = RandomTicker {
+            mean_interval: to_nanos(mean_interval),
+            until_next: 0,
+            rng,
+        };
+        ticker.until_next = ticker.interval();
+        ticker
+    }
+
+    /// Gets the average time between events.
+    pub fn mean_interval(&self) -> Duration {
+        Duration::from_nanos(self.mean_interval)
+    }
+
+    /// Draws an exponentially distributed time between events, never zero.
+    fn interval(&mut self) -> u64 {
+        let draw = -(1.0 - self.rng.next_f64()).ln() * self.mean_interval as f64;
+        (draw as u64).max(1)
+    }
+
+    /// Advances by `delta` of game time and returns how many events fire.
+    pub fn sample(&mut self, delta: Duration) -> u32 {
+        let mut left = to_nanos(delta);
+        let mut fired = 0;
+        while left >= self.until_next {
+            left -= self.until_next;
+            fired += 1;
+            self.until_next = self.interval();
+        }
+        self.until_next -= left;
+        fired
+    }
+
+    /// Advances by the scaled delta of the frame and returns how many events fire.
+    /// Nothing fires while the game is paused.
+    pub fn update(&mut self, time: &Time) -> u32 {
+        self.sample(time.delta_time())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn average_rate_independent_of_frame_rate() {
+        let count = |frame_millis: u64| {
+            let mut ticker = RandomTicker::with_mean_interval_and_seed(Duration::from_secs(1), 5);
+            (0..1_000_000 / frame_millis)
+                .map(|_| ticker.sample(Duration::from_millis(frame_millis)))
+                .sum::<u32>()
+        };
+        // 1000 seconds at one event per second.
+        for frame_millis in &[1, 16, 100] {
+            assert!((900..1100).contains(&count(*frame_millis)));
+        }
+        let mut ticker = RandomTicker::with_mean_interval_and_seed(Duration::from_secs(1), 5);
+        assert_eq!(ticker.sample(Duration::from_secs(1_000)), count(1_000_000),);
+
+        let mut time = Time::default();
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(1_000));
+        assert_eq!(ticker.update(&time), 0);
+    }
+}
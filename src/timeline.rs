@@ -0,0 +1,213 @@
+//! Cue points and ranges placed on the scaled timeline, for cutscenes and scripted sequences.
+
+use std::time::Duration;
+
+use crate::Time;
+
+/// Something a `Timeline` passed since its last update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimelineEvent<P> {
+    /// A cue point was reached.
+    Cue {
+        /// Label of the cue.
+        label: String,
+        /// Payload of the cue.
+        payload: P,
+    },
+    /// The start of a range was reached.
+    Entered {
+        /// Label of the range.
+        label: String,
+        /// Payload of the range.
+        payload: P,
+    },
+    /// The end of a range was reached.
+    Exited {
+        /// Label of the range.
+        label: String,
+        /// Payload of the range.
+        payload: P,
+    },
+}
+
+/// A cue point or a range of a `Timeline`.
+#[derive(Clone, Debug, PartialEq)]
+struct Marker<P> {
+    /// Position of the cue, or start of the range.
+    start: Duration,
+    /// End of the range. `None` for cue points.
+    end: Option<Duration>,
+    label: String,
+    payload: P,
+}
+
+/// Labeled cue points and ranges positioned in scaled game time from the start of the
+/// timeline. Each update reports the cues and range boundaries passed since the previous one.
+///
+/// Because it follows `Time::absolute_time`, pausing the game pauses the timeline.
+/// When a single frame passes several markers, they are reported in chronological order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timeline<P> {
+    /// Scaled absolute time of the clock at the start of the timeline.
+    start: Duration,
+    markers: Vec<Marker<P>>,
+    /// Position of the timeline at the last update. `None` before the first update.
+    position: Option<Duration>,
+}
+
+impl<P: Clone> Timeline<P> {
+    /// Creates an empty timeline starting at the current time of the clock.
+    pub fn new(time: &Time) -> Self {
+        Timeline {
+            start: time.absolute_time(),
+            markers: vec![],
+            position: None,
+        }
+    }
+
+    /// Adds a cue point reached `at` after the start of the timeline.
+    pub fn add_cue(&mut self, at: Duration, label: impl Into<String>, payload: P) {
+        self.markers.push(Marker {
+            start: at,
+            end: None,
+            label: label.into(),
+            payload,
+        });
+    }
+
+    /// Adds a range entered at `start` and exited at `end`, relative to the start of the
+    /// timeline.
+    ///
+    /// ## Panics
+    /// This will panic if end is before start.
+    pub fn add_range(
+        &mut self,
+        start: Duration,
+        end: Duration,
+        label: impl Into<String>,
+        payload: P,
+    ) {
+        assert!(start <= end);
+        self.markers.push(Marker {
+            start,
+            end: Some(end),
+            label: label.into(),
+            payload,
+        });
+    }
+
+    /// Gets the position of the timeline at the last update.
+    pub fn position(&self) -> Duration {
+        self.position.unwrap_or_default()
+    }
+
+    /// Gets the time of the last cue or range end.
+    pub fn duration(&self) -> Duration {
+        self.markers
+            .iter()
+            .map(|marker| marker.end.unwrap_or(marker.start))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Checks if the timeline passed its last cue or range end.
+    pub fn is_finished(&self) -> bool {
+        self.position
+            .is_some_and(|position| position >= self.duration())
+    }
+
+    /// Gets the labels of the ranges the timeline is currently in.
+    pub fn active_ranges(&self) -> impl Iterator<Item = &str> {
+        let position = self.position;
+        self.markers
+            .iter()
+            .filter_map(move |marker| match (position, marker.end) {
+                (Some(position), Some(end)) if marker.start <= position && position < end => {
+                    Some(marker.label.as_str())
+                }
+                _ => None,
+            })
+    }
+
+    /// Gets the cues and range boundaries passed since the last update.
+    pub fn update(&mut self, time: &Time) -> Vec<TimelineEvent<P>> {
+        let now = time
+            .absolute_time()
+            .checked_sub(self.start)
+            .unwrap_or_default();
+        let last = self.position;
+        let passed = |at: Duration| last.is_none_or(|last| last < at) && at <= now;
+        let mut events = vec![];
+        for marker in &self.markers {
+            let label = || marker.label.clone();
+            let payload = || marker.payload.clone();
+            if passed(marker.start) {
+                let event = match marker.end {
+                    None => TimelineEvent::Cue {
+                        label: label(),
+                        payload: payload(),
+                    },
+                    Some(_) => TimelineEvent::Entered {
+                        label: label(),
+                        payload: payload(),
+                    },
+                };
+                events.push((marker.start, event));
+            }
+            if let Some(end) = marker.end.filter(|end| passed(*end)) {
+                let event = TimelineEvent::Exited {
+                    label: label(),
+                    payload: payload(),
+                };
+                events.push((end, event));
+            }
+        }
+        self.position = Some(now);
+        events.sort_by_key(|(at, _)| *at);
+        events.into_iter().map(|(_, event)| event).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn cues_and_ranges_in_order() {
+        let mut time = Time::default();
+        let mut timeline = Timeline::new(&time);
+        timeline.add_range(Duration::from_secs(1), Duration::from_secs(3), "dialog", 1);
+        timeline.add_cue(Duration::from_secs(2), "explosion", 2);
+        timeline.add_cue(Duration::from_secs(0), "fade_in", 3);
+
+        assert_eq!(
+            timeline.update(&time),
+            vec![TimelineEvent::Cue {
+                label: "fade_in".to_string(),
+                payload: 3
+            }]
+        );
+
+        time.advance_frame(Duration::from_millis(2_500));
+        let events = timeline.update(&time);
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            TimelineEvent::Entered {
+                label: "dialog".to_string(),
+                payload: 1
+            }
+        );
+        assert_eq!(timeline.active_ranges().collect::<Vec<_>>(), vec!["dialog"]);
+
+        time.set_time_scale(0.0);
+        time.advance_frame(Duration::from_secs(10));
+        assert!(timeline.update(&time).is_empty());
+
+        time.set_time_scale(1.0);
+        time.advance_frame(Duration::from_secs(1));
+        assert_eq!(timeline.update(&time).len(), 1);
+        assert!(timeline.is_finished());
+    }
+}
@@ -0,0 +1,138 @@
+//! Compact timestamps of the scaled game timeline, for save files.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::{GameDuration, GameInstant};
+
+/// A point on the scaled game timeline, stored as whole nanoseconds since game start.
+///
+/// Unlike `GameInstant`, its stored form is part of the API: `to_bytes` and `Display` give
+/// the same value whatever the internal representation of `Time`, so world events stamped
+/// in one session still compare correctly after loading the save in another.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameTimestamp(u64);
+
+impl GameTimestamp {
+    /// The start of the game.
+    pub const START: GameTimestamp = GameTimestamp(0);
+
+    /// Creates the timestamp the given number of scaled nanoseconds after game start.
+    pub const fn from_nanos(nanos: u64) -> Self {
+        GameTimestamp(nanos)
+    }
+
+    /// Gets the number of scaled nanoseconds since game start.
+    pub const fn as_nanos(self) -> u64 {
+        self.0
+    }
+
+    /// Gets the stored form of the timestamp: the nanoseconds as little-endian bytes.
+    pub const fn to_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    /// Reads a timestamp written by `to_bytes`.
+    pub const fn from_bytes(bytes: [u8; 8]) -> Self {
+        GameTimestamp(u64::from_le_bytes(bytes))
+    }
+
+    /// Gets the game time elapsed from `earlier` to this timestamp, or zero if `earlier` is
+    /// later.
+    pub fn saturating_duration_since(self, earlier: GameTimestamp) -> GameDuration {
+        GameInstant::from(self).saturating_duration_since(earlier.into())
+    }
+}
+
+impl From<GameInstant> for GameTimestamp {
+    /// Saturates to the largest timestamp, about 584 years after game start.
+    fn from(instant: GameInstant) -> Self {
+        GameTimestamp(crate::to_nanos(instant.since_start()))
+    }
+}
+
+impl From<GameTimestamp> for GameInstant {
+    fn from(timestamp: GameTimestamp) -> Self {
+        GameInstant::from_start(std::time::Duration::from_nanos(timestamp.0))
+    }
+}
+
+/// Saturates to the largest timestamp.
+impl Add<GameDuration> for GameTimestamp {
+    type Output = GameTimestamp;
+
+    fn add(self, rhs: GameDuration) -> GameTimestamp {
+        (GameInstant::from(self) + rhs).into()
+    }
+}
+
+impl AddAssign<GameDuration> for GameTimestamp {
+    fn add_assign(&mut self, rhs: GameDuration) {
+        *self = *self + rhs;
+    }
+}
+
+/// ## Panics
+/// This will panic if the result is before game start.
+impl Sub<GameDuration> for GameTimestamp {
+    type Output = GameTimestamp;
+
+    fn sub(self, rhs: GameDuration) -> GameTimestamp {
+        (GameInstant::from(self) - rhs).into()
+    }
+}
+
+impl SubAssign<GameDuration> for GameTimestamp {
+    fn sub_assign(&mut self, rhs: GameDuration) {
+        *self = *self - rhs;
+    }
+}
+
+/// Saturates to zero if `rhs` is later, like `std::time::Instant`.
+impl Sub for GameTimestamp {
+    type Output = GameDuration;
+
+    fn sub(self, rhs: GameTimestamp) -> GameDuration {
+        self.saturating_duration_since(rhs)
+    }
+}
+
+/// Writes the nanoseconds since game start, like `1500000000`.
+impl fmt::Display for GameTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for GameTimestamp {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        text.parse().map(GameTimestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamp_round_trip() {
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(750));
+        let stamp = time.timestamp();
+        assert_eq!(stamp, GameTimestamp::from_nanos(1_500_000_000));
+        assert_eq!(GameTimestamp::from_bytes(stamp.to_bytes()), stamp);
+        assert_eq!(stamp.to_string().parse(), Ok(stamp));
+        assert!("soon".parse::<GameTimestamp>().is_err());
+
+        let later = stamp + GameDuration::new(Duration::from_secs(1));
+        assert!(later > stamp);
+        assert_eq!(later - stamp, GameDuration::new(Duration::from_secs(1)));
+        assert_eq!(stamp - later, GameDuration::ZERO);
+        assert_eq!(GameInstant::from(stamp), time.game_now());
+    }
+}
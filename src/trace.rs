@@ -0,0 +1,338 @@
+//! Recording and replaying the timing of a run, for golden tests of fixed-step schedules and
+//! replay viewers.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Time;
+
+/// Timing values of one frame of a `TimingTrace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceFrame {
+    /// Real time that passed during the frame, before the minimum delta, in nanoseconds.
+    pub delta_real_nanos: u64,
+    /// Scaled time that passed during the frame, before the minimum delta, in nanoseconds.
+    pub delta_nanos: u64,
+    /// Time scale of the frame, as `(numerator, denominator)`.
+    pub time_scale: (u64, u64),
+    /// Number of fixed steps run during the frame.
+    pub fixed_steps: u32,
+}
+
+impl TraceFrame {
+    fn capture(time: &Time) -> Self {
+        TraceFrame {
+            delta_real_nanos: time.frame_real_time,
+            delta_nanos: time.frame_time,
+            time_scale: time.time_scale_ratio(),
+            fixed_steps: time.fixed_steps_this_frame(),
+        }
+    }
+
+    /// Advances `time` like the recorded frame, running every fixed step that is due.
+    fn play(&self, time: &mut Time, on_fixed_step: &mut impl FnMut(&Time)) {
+        let (numerator, denominator) = self.time_scale;
+        if time.time_scale_ratio() != self.time_scale {
+            time.set_time_scale_ratio(numerator, denominator);
+        }
+        time.advance_frame(Duration::from_nanos(self.delta_real_nanos));
+        while time.step_fixed_update() {
+            on_fixed_step(time);
+        }
+    }
+}
+
+/// A replayed frame that did not match the trace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceMismatch {
+    /// Index of the frame in the trace.
+    pub frame: usize,
+    /// The frame as recorded.
+    pub expected: TraceFrame,
+    /// The frame as replayed.
+    pub actual: TraceFrame,
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "frame {} differs: expected {:?}, got {:?}",
+            self.frame, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for TraceMismatch {}
+
+/// A line of a stored trace that could not be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceParseError {
+    /// Number of the line, starting at 1.
+    pub line: usize,
+}
+
+impl fmt::Display for TraceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid timing trace at line {}", self.line)
+    }
+}
+
+impl std::error::Error for TraceParseError {}
+
+/// The deltas, time scales and fixed steps of every frame of a run.
+///
+/// Record a run once, store it as text next to the tests, and `replay` it in CI: any change
+/// to the fixed-step schedule or to scaled deltas is reported as a `TraceMismatch`.
+///
+/// For the trace to replay, time scale changes must happen before `advance_frame`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TimingTrace {
+    frames: Vec<TraceFrame>,
+}
+
+impl TimingTrace {
+    /// Creates an empty trace.
+    pub fn new() -> Self {
+        TimingTrace::default()
+    }
+
+    /// Records the current frame of the clock. Call once per frame, after the fixed updates.
+    pub fn record(&mut self, time: &Time) {
+        self.frames.push(TraceFrame::capture(time));
+    }
+
+    /// Gets the recorded frames.
+    pub fn frames(&self) -> &[TraceFrame] {
+        &self.frames
+    }
+
+    /// Plays the trace on `time`, running every fixed step that is due, and checks that each
+    /// frame matches the recording. `time` should be configured like the recorded clock
+    /// (fixed time step, catch-up policy), without scale layers.
+    pub fn replay(&self, time: &mut Time) -> Result<(), TraceMismatch> {
+        self.replay_with(time, |_| {})
+    }
+
+    /// Like `replay`, calling `on_fixed_step` for every fixed step.
+    pub fn replay_with(
+        &self,
+        time: &mut Time,
+        mut on_fixed_step: impl FnMut(&Time),
+    ) -> Result<(), TraceMismatch> {
+        for (index, expected) in self.frames.iter().enumerate() {
+            expected.play(time, &mut on_fixed_step);
+            let actual = TraceFrame::capture(time);
+            if actual != *expected {
+                return Err(TraceMismatch {
+                    frame: index,
+                    expected: *expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plays a `TimingTrace` back on a clock from any point, for the seek bar of replay viewers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TracePlayer {
+    trace: TimingTrace,
+    /// The clock before the first frame, to rewind to.
+    start: Time,
+    time: Time,
+    /// Number of frames played.
+    position: usize,
+}
+
+impl TracePlayer {
+    /// Creates a player at the start of the trace. `time` should be configured like the
+    /// recorded clock before its first frame. See `TimingTrace::replay`.
+    pub fn new(trace: TimingTrace, time: Time) -> Self {
+        TracePlayer {
+            trace,
+            start: time.clone(),
+            time,
+            position: 0,
+        }
+    }
+
+    /// Gets the played trace.
+    pub fn trace(&self) -> &TimingTrace {
+        &self.trace
+    }
+
+    /// Gets the clock, as it was after the last played frame.
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Gets the number of frames played.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Plays the next frame, calling `on_fixed_step` for every fixed step.
+    /// Returns false if the trace is over.
+    pub fn play_frame(&mut self, mut on_fixed_step: impl FnMut(&Time)) -> bool {
+        match self.trace.frames.get(self.position) {
+            Some(frame) => {
+                frame.play(&mut self.time, &mut on_fixed_step);
+                self.position += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fast-forwards to the first frame ending at or after the scaled absolute time `target`,
+    /// or to the end of the trace, calling `on_fixed_step` for every fixed step in between.
+    ///
+    /// Clocks can't run backwards: seeking before the current time rewinds to the start of
+    /// the trace and plays it again, so the fixed steps run from the beginning.
+    pub fn scrub_to(&mut self, target: Duration, mut on_fixed_step: impl FnMut(&Time)) {
+        if target < self.time.absolute_time() {
+            self.time = self.start.clone();
+            self.position = 0;
+        }
+        while self.time.absolute_time() < target && self.play_frame(&mut on_fixed_step) {}
+    }
+}
+
+/// Writes one frame per line: real delta, scaled delta, time scale and fixed steps,
+/// like `16666666 33333332 2/1 1`.
+impl fmt::Display for TimingTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in &self.frames {
+            writeln!(
+                f,
+                "{} {} {}/{} {}",
+                frame.delta_real_nanos,
+                frame.delta_nanos,
+                frame.time_scale.0,
+                frame.time_scale.1,
+                frame.fixed_steps
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TimingTrace {
+    type Err = TraceParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut frames = vec![];
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = TraceParseError { line: index + 1 };
+            let mut fields = line.split_whitespace();
+            let mut next = || fields.next().ok_or(error);
+            let delta_real_nanos = next()?.parse().map_err(|_| error)?;
+            let delta_nanos = next()?.parse().map_err(|_| error)?;
+            let mut scale = next()?.splitn(2, '/');
+            let mut scale_part = || -> Result<u64, TraceParseError> {
+                scale.next().ok_or(error)?.parse().map_err(|_| error)
+            };
+            let time_scale = (scale_part()?, scale_part()?);
+            let fixed_steps = next()?.parse().map_err(|_| error)?;
+            if time_scale.1 == 0 || fields.next().is_some() {
+                return Err(error);
+            }
+            frames.push(TraceFrame {
+                delta_real_nanos,
+                delta_nanos,
+                time_scale,
+                fixed_steps,
+            });
+        }
+        Ok(TimingTrace { frames })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    fn clock() -> Time {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.set_catch_up(CatchUp::Smooth { max_extra_steps: 1 });
+        time
+    }
+
+    #[test]
+    fn golden_trace_round_trip() {
+        let mut time = clock();
+        let mut trace = TimingTrace::new();
+        for (delta, scale) in &[(16, 1.0), (100, 1.0), (16, 0.5), (16, 0.5)] {
+            time.set_time_scale(*scale);
+            time.advance_frame(Duration::from_millis(*delta));
+            while time.step_fixed_update() {}
+            trace.record(&time);
+        }
+
+        let stored = trace.to_string();
+        assert_eq!(stored.lines().nth(2), Some("16000000 8000000 1/2 3"));
+        let loaded: TimingTrace = stored.parse().unwrap();
+        assert_eq!(loaded, trace);
+        let mut steps = 0;
+        loaded.replay_with(&mut clock(), |_| steps += 1).unwrap();
+        assert_eq!(steps, 10);
+
+        // Frames raised to the minimum delta record the time that passed, not the reported delta.
+        let raised = || {
+            let mut time = clock();
+            time.set_min_delta(Duration::from_millis(20));
+            time
+        };
+        let mut recorded = raised();
+        let mut trace = TimingTrace::new();
+        for delta in &[16, 100, 16] {
+            recorded.advance_frame(Duration::from_millis(*delta));
+            while recorded.step_fixed_update() {}
+            trace.record(&recorded);
+        }
+        assert_eq!(trace.frames()[0].delta_real_nanos, 16_000_000);
+        let mut replayed = raised();
+        trace.replay(&mut replayed).unwrap();
+        assert_eq!(replayed, recorded);
+
+        let mut changed = clock();
+        changed.set_catch_up(CatchUp::Immediate);
+        assert_eq!(loaded.replay(&mut changed).unwrap_err().frame, 1);
+        assert_eq!(
+            "1 2 3".parse::<TimingTrace>(),
+            Err(TraceParseError { line: 1 })
+        );
+    }
+
+    #[test]
+    fn scrub_back_and_forth() {
+        let mut time = clock();
+        let mut trace = TimingTrace::new();
+        for _ in 0..100 {
+            time.advance_frame(Duration::from_millis(16));
+            while time.step_fixed_update() {}
+            trace.record(&time);
+        }
+
+        let mut player = TracePlayer::new(trace, clock());
+        let mut steps = 0;
+        player.scrub_to(Duration::from_millis(800), |_| steps += 1);
+        assert_eq!(player.position(), 50);
+        assert_eq!(steps, 80);
+        player.scrub_to(Duration::from_millis(100), |_| steps += 1);
+        assert_eq!(player.position(), 7);
+        assert_eq!(player.time().absolute_time(), Duration::from_millis(112));
+        assert_eq!(player.time().fixed_step_count(), 11);
+        player.scrub_to(Duration::from_secs(60), |_| {});
+        assert_eq!(player.position(), 100);
+        assert_eq!(player.time(), &time);
+    }
+}
@@ -0,0 +1,114 @@
+//! Keyframed automation of the time scale.
+
+use std::time::Duration;
+
+use crate::Easing;
+
+/// A time scale reached at a given point of a `ScaleTrack`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaleKey {
+    /// Real time since the start of the track.
+    pub time: Duration,
+    /// Time scale reached at `time`.
+    pub scale: f32,
+    /// How the scale moves from the previous key to this one.
+    pub easing: Easing,
+}
+
+/// Keyframed time scale, for scripted slow motion sequences and cinematic speed ramps.
+///
+/// Play it on a clock with `Time::play_scale_track`. Keys are positioned in real time, so a
+/// track slowing time down to 0 still reaches its next keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScaleTrack {
+    /// Keys ordered by time.
+    keys: Vec<ScaleKey>,
+}
+
+impl ScaleTrack {
+    /// Creates a track without keys, which keeps the scale at 1.
+    pub fn new() -> Self {
+        ScaleTrack::default()
+    }
+
+    /// Adds a key reaching `scale` at `time`, moving from the previous key with `easing`.
+    /// A key added at the time of an existing key goes after it.
+    ///
+    /// ## Panics
+    /// This will panic if scale is NaN, Infinity, or less than 0.
+    pub fn add_key(&mut self, time: Duration, scale: f32, easing: Easing) {
+        assert!(scale >= 0.0);
+        assert!(scale != f32::INFINITY);
+        let index = self.keys.partition_point(|key| key.time <= time);
+        self.keys.insert(
+            index,
+            ScaleKey {
+                time,
+                scale,
+                easing,
+            },
+        );
+    }
+
+    /// Gets the keys, ordered by time.
+    pub fn keys(&self) -> &[ScaleKey] {
+        &self.keys
+    }
+
+    /// Gets the time of the last key.
+    pub fn duration(&self) -> Duration {
+        self.keys.last().map(|key| key.time).unwrap_or_default()
+    }
+
+    /// Gets the scale at the given real time since the start of the track.
+    /// Before the first key this is the scale of the first key, and after the last key the
+    /// scale of the last key.
+    pub fn sample(&self, elapsed: Duration) -> f32 {
+        let next = self.keys.partition_point(|key| key.time <= elapsed);
+        match (
+            next.checked_sub(1).map(|i| self.keys[i]),
+            self.keys.get(next),
+        ) {
+            (None, None) => 1.0,
+            (None, Some(next)) => next.scale,
+            (Some(previous), None) => previous.scale,
+            (Some(previous), Some(next)) => {
+                let t = (elapsed - previous.time).as_secs_f64()
+                    / (next.time - previous.time).as_secs_f64();
+                let eased = next.easing.apply(t as f32);
+                previous.scale + (next.scale - previous.scale) * eased
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn track_drives_time_scale() {
+        let mut track = ScaleTrack::new();
+        track.add_key(Duration::from_secs(1), 1.0, Easing::Linear);
+        track.add_key(Duration::from_secs(2), 0.0, Easing::Linear);
+        track.add_key(Duration::from_secs(3), 1.0, Easing::Step);
+        assert_eq!(track.sample(Duration::from_millis(1_500)), 0.5);
+        assert_eq!(track.sample(Duration::from_millis(2_500)), 0.0);
+        assert_eq!(track.duration(), Duration::from_secs(3));
+
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.play_scale_track(track);
+        time.advance_frame(Duration::from_millis(1_500));
+        assert_eq!(time.delta_time(), Duration::from_secs(3));
+        assert_eq!(time.time_scale(), 1.0);
+        time.advance_frame(Duration::from_millis(1_000));
+        assert_eq!(time.time_scale(), 0.0);
+        time.advance_frame(Duration::from_millis(1_000));
+        assert_eq!(time.time_scale(), 2.0);
+
+        time.stop_scale_track();
+        assert!(time.scale_track().is_none());
+    }
+}
@@ -0,0 +1,187 @@
+//! Read-only access to clocks, for plugin, script and mod APIs.
+
+use std::time::Duration;
+
+use crate::{PackedTime, SharedTime, Time};
+
+/// A clock that can be read but not changed, usable as `&dyn GameClock`.
+///
+/// Plugin APIs can hand out a `&dyn GameClock` without committing to the type of the clock,
+/// or letting the plugin advance it or change its scale.
+pub trait GameClock {
+    /// Gets a consistent snapshot of the values of the clock. The other getters may read
+    /// values of different frames when the clock belongs to another thread.
+    fn pack(&self) -> PackedTime;
+
+    /// Gets the time difference between frames.
+    fn delta_time(&self) -> Duration {
+        self.pack().delta_time()
+    }
+
+    /// Gets the time difference between frames ignoring the time speed multiplier.
+    fn delta_real_time(&self) -> Duration {
+        self.pack().delta_real_time()
+    }
+
+    /// Gets the time since the start of the game, taking into account the speed multiplier.
+    fn absolute_time(&self) -> Duration {
+        self.pack().absolute_time()
+    }
+
+    /// Gets the time since the start of the game, ignoring the speed multiplier.
+    fn absolute_real_time(&self) -> Duration {
+        self.pack().absolute_real_time()
+    }
+
+    /// Gets the current frame number.
+    fn frame_number(&self) -> u64 {
+        self.pack().frame_number
+    }
+}
+
+impl GameClock for Time {
+    fn pack(&self) -> PackedTime {
+        Time::pack(self)
+    }
+
+    fn delta_time(&self) -> Duration {
+        Time::delta_time(self)
+    }
+
+    fn delta_real_time(&self) -> Duration {
+        Time::delta_real_time(self)
+    }
+
+    fn absolute_time(&self) -> Duration {
+        Time::absolute_time(self)
+    }
+
+    fn absolute_real_time(&self) -> Duration {
+        Time::absolute_real_time(self)
+    }
+
+    fn frame_number(&self) -> u64 {
+        Time::frame_number(self)
+    }
+}
+
+/// Reads the last published values.
+impl GameClock for SharedTime {
+    fn pack(&self) -> PackedTime {
+        self.load()
+    }
+}
+
+impl GameClock for PackedTime {
+    fn pack(&self) -> PackedTime {
+        *self
+    }
+}
+
+/// A borrow of a clock exposing only its getters, obtained from `Time::view`.
+///
+/// Pass it to user scripts and mods so they can read timing, without reaching the rest of the
+/// API of the clock. It is `Copy`, and as cheap to pass around as a reference.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeView<'a> {
+    time: &'a Time,
+}
+
+impl<'a> TimeView<'a> {
+    pub(crate) fn new(time: &'a Time) -> Self {
+        TimeView { time }
+    }
+
+    /// Gets the time difference between frames.
+    pub fn delta_time(&self) -> Duration {
+        self.time.delta_time()
+    }
+
+    /// Gets the time difference between frames ignoring the time speed multiplier.
+    pub fn delta_real_time(&self) -> Duration {
+        self.time.delta_real_time()
+    }
+
+    /// Gets the time since the start of the game, taking into account the speed multiplier.
+    pub fn absolute_time(&self) -> Duration {
+        self.time.absolute_time()
+    }
+
+    /// Gets the time since the start of the game, ignoring the speed multiplier.
+    pub fn absolute_real_time(&self) -> Duration {
+        self.time.absolute_real_time()
+    }
+
+    /// Gets the current frame number.
+    pub fn frame_number(&self) -> u64 {
+        self.time.frame_number()
+    }
+
+    /// Gets the current time speed multiplier, including every scale layer.
+    pub fn time_scale(&self) -> f32 {
+        self.time.time_scale()
+    }
+
+    /// Gets the fixed time step.
+    pub fn fixed_time(&self) -> Duration {
+        self.time.fixed_time()
+    }
+
+    /// Gets the number of fixed steps run since the clock was created.
+    pub fn fixed_step_count(&self) -> u64 {
+        self.time.fixed_step_count()
+    }
+
+    /// Gets how far the simulation is between the last fixed step and the next one, from 0 to 1.
+    pub fn fixed_step_alpha(&self) -> f64 {
+        self.time.fixed_step_alpha()
+    }
+}
+
+impl GameClock for TimeView<'_> {
+    fn pack(&self) -> PackedTime {
+        self.time.pack()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    fn plugin_update(clock: &dyn GameClock) -> (u64, Duration) {
+        (clock.frame_number(), clock.absolute_time())
+    }
+
+    #[test]
+    fn clocks_behind_trait_objects() {
+        let mut time = Time::default();
+        time.set_time_scale(2.0);
+        time.advance_frame(Duration::from_millis(10));
+        let shared = SharedTime::new();
+        shared.publish(&time);
+
+        let expected = (1, Duration::from_millis(20));
+        assert_eq!(plugin_update(&time), expected);
+        assert_eq!(plugin_update(&shared), expected);
+        assert_eq!(plugin_update(&time.pack()), expected);
+        assert_eq!(
+            GameClock::delta_real_time(&shared),
+            Duration::from_millis(10)
+        );
+    }
+
+    #[test]
+    fn read_only_view() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(10));
+        time.advance_frame(Duration::from_millis(25));
+        while time.step_fixed_update() {}
+
+        let view = time.view();
+        let script = move || (view.fixed_step_count(), view.fixed_step_alpha());
+        assert_eq!(script(), (2, 0.5));
+        assert_eq!(view.time_scale(), 1.0);
+        assert_eq!(plugin_update(&view), (1, Duration::from_millis(25)));
+    }
+}
@@ -0,0 +1,154 @@
+//! Detection of game loops that silently stopped advancing the clock.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{ClockSource, SystemClock, Time};
+
+/// A stalled game loop, reported by `Watchdog::check`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LoopStall {
+    /// Time since the clock was last advanced.
+    pub stalled_for: Duration,
+    /// Number of the last frame the clock was advanced to.
+    pub frame_number: u64,
+}
+
+impl fmt::Display for LoopStall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "advance_frame not called for {:?} since frame {}",
+            self.stalled_for, self.frame_number
+        )
+    }
+}
+
+#[derive(Debug)]
+struct WatchState {
+    /// When `record` was last called.
+    last_advance: Instant,
+    frame_number: u64,
+    /// False while the app doesn't claim to run its loop.
+    running: bool,
+    /// True once the current stall has been reported.
+    reported: bool,
+}
+
+/// Detects when `advance_frame` hasn't been called for longer than a threshold while the app
+/// claims to be running, which otherwise only shows up as frozen timers.
+///
+/// Share it with an `Arc`: the game loop calls `record` after every `advance_frame`, and
+/// another thread calls `check` periodically to surface stalls in logs or a debug overlay.
+#[derive(Debug)]
+pub struct Watchdog<S: ClockSource = SystemClock> {
+    source: S,
+    threshold: Duration,
+    state: Mutex<WatchState>,
+}
+
+impl Watchdog {
+    /// Creates a watchdog reporting loops stalled for longer than `threshold`, reading the
+    /// time from `Instant::now`.
+    pub fn new(threshold: Duration) -> Self {
+        Watchdog::with_source(SystemClock, threshold)
+    }
+}
+
+impl<S: ClockSource> Watchdog<S> {
+    /// Creates a watchdog reading the time from `source`. The app is assumed to be running,
+    /// with the clock just advanced.
+    pub fn with_source(source: S, threshold: Duration) -> Self {
+        let now = source.now();
+        Watchdog {
+            source,
+            threshold,
+            state: Mutex::new(WatchState {
+                last_advance: now,
+                frame_number: 0,
+                running: true,
+                reported: false,
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, WatchState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Gets the time without advance above which the loop is considered stalled.
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Notes that the clock was advanced. Call it after every `advance_frame`.
+    pub fn record(&self, time: &Time) {
+        let now = self.source.now();
+        let mut state = self.state();
+        state.last_advance = now;
+        state.frame_number = time.frame_number();
+        state.reported = false;
+    }
+
+    /// Tells whether the app claims to run its loop. Set it to false while the loop is
+    /// deliberately stopped, like during a blocking dialog or while suspended. Turning it back
+    /// on restarts the wait.
+    pub fn set_running(&self, running: bool) {
+        let now = self.source.now();
+        let mut state = self.state();
+        if running && !state.running {
+            state.last_advance = now;
+            state.reported = false;
+        }
+        state.running = running;
+    }
+
+    /// Reports a stall if the clock hasn't been advanced for longer than the threshold while
+    /// running. Each stall is reported once.
+    pub fn check(&self) -> Option<LoopStall> {
+        let now = self.source.now();
+        let mut state = self.state();
+        let stalled_for = now.saturating_duration_since(state.last_advance);
+        if !state.running || state.reported || stalled_for <= self.threshold {
+            return None;
+        }
+        state.reported = true;
+        Some(LoopStall {
+            stalled_for,
+            frame_number: state.frame_number,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn reports_stalled_loop_once() {
+        let clock = ManualClock::new(Instant::now());
+        let watchdog = Watchdog::with_source(clock.clone(), Duration::from_secs(1));
+        let mut time = Time::default();
+        time.advance_frame(Duration::from_millis(16));
+        watchdog.record(&time);
+
+        clock.advance(Duration::from_millis(900));
+        assert_eq!(watchdog.check(), None);
+        clock.advance(Duration::from_millis(200));
+        let stall = watchdog.check().unwrap();
+        assert_eq!(stall.frame_number, 1);
+        assert_eq!(stall.stalled_for, Duration::from_millis(1_100));
+        assert_eq!(watchdog.check(), None);
+
+        // Deliberate stops are not stalls.
+        watchdog.set_running(false);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(watchdog.check(), None);
+        watchdog.set_running(true);
+        assert_eq!(watchdog.check(), None);
+        clock.advance(Duration::from_secs(2));
+        assert!(watchdog.check().is_some());
+    }
+}
@@ -0,0 +1,273 @@
+//! Hierarchical timer wheel backing the scheduler when many calls are pending.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Bits of the tick used to index the slots of one level.
+const BITS: u32 = 6;
+const SLOTS: usize = 1 << BITS;
+const MASK: u64 = SLOTS as u64 - 1;
+/// Number of levels. Calls further than `SLOTS^LEVELS` ticks away wait in the overflow list.
+const LEVELS: usize = 4;
+
+/// Span of ticks covered by the given number of levels.
+fn span(levels: usize) -> u64 {
+    1 << (BITS as usize * levels)
+}
+
+/// Hashed hierarchical timer wheel with O(1) insertion.
+///
+/// Items are `(due, id)` pairs, with due times in nanoseconds.
+/// Expired items are sorted in a small heap so they come out in due order.
+#[derive(Clone, Debug)]
+pub(crate) struct TimerWheel {
+    /// Length of a tick in nanoseconds.
+    resolution: u64,
+    /// Last tick that was processed.
+    current: u64,
+    /// Items by level, then by slot.
+    slots: Vec<Vec<Vec<(u64, u64)>>>,
+    /// Number of items stored in each level.
+    counts: [usize; LEVELS],
+    /// Items too far in the future for the wheel.
+    overflow: Vec<(u64, u64)>,
+    /// Items whose tick was processed, soonest first.
+    ready: BinaryHeap<Reverse<(u64, u64)>>,
+}
+
+impl TimerWheel {
+    pub(crate) fn new(resolution: u64) -> Self {
+        TimerWheel {
+            resolution: resolution.max(1),
+            current: 0,
+            slots: vec![vec![vec![]; SLOTS]; LEVELS],
+            counts: [0; LEVELS],
+            overflow: vec![],
+            ready: BinaryHeap::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, due: u64, id: u64) {
+        let tick = due / self.resolution;
+        if tick <= self.current {
+            self.ready.push(Reverse((due, id)));
+            return;
+        }
+        let delta = tick - self.current;
+        match (0..LEVELS).find(|level| delta < span(level + 1)) {
+            Some(level) => {
+                let slot = (tick >> (BITS as usize * level)) & MASK;
+                self.slots[level][slot as usize].push((due, id));
+                self.counts[level] += 1;
+            }
+            None => self.overflow.push((due, id)),
+        }
+    }
+
+    /// Removes the soonest item due at or before `now`.
+    pub(crate) fn pop_due(&mut self, now: u64) -> Option<(u64, u64)> {
+        self.advance(now / self.resolution);
+        match self.ready.peek() {
+            Some(&Reverse((due, id))) if due <= now => {
+                self.ready.pop();
+                Some((due, id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the due time of the soonest item for which `live` returns true, without
+    /// processing any tick. Expired items that are not live are removed, and counted in
+    /// `removed`.
+    ///
+    /// Only the first occupied slot of each level is searched, along with the overflow list.
+    pub(crate) fn peek(
+        &mut self,
+        live: impl Fn(u64, u64) -> bool,
+        removed: &mut usize,
+    ) -> Option<u64> {
+        while let Some(&Reverse((due, id))) = self.ready.peek() {
+            if live(due, id) {
+                return Some(due);
+            }
+            self.ready.pop();
+            *removed += 1;
+        }
+
+        let soonest = |items: &[(u64, u64)]| {
+            items
+                .iter()
+                .filter(|&&(due, id)| live(due, id))
+                .map(|&(due, _)| due)
+                .min()
+        };
+        let mut next = soonest(&self.overflow);
+        for level in 0..LEVELS {
+            if self.counts[level] == 0 {
+                continue;
+            }
+            // Items of a level are less than a full turn of the level ahead of the current
+            // tick, so the first occupied slot after it holds the soonest ones.
+            let first = (self.current >> (BITS as usize * level)) + 1;
+            let level_next = (0..SLOTS as u64)
+                .map(|offset| ((first + offset) & MASK) as usize)
+                .find_map(|slot| soonest(&self.slots[level][slot]));
+            next = match (next, level_next) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        next
+    }
+
+    /// Keeps only the items for which `keep` returns true.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(u64, u64) -> bool) {
+        for (level, slots) in self.slots.iter_mut().enumerate() {
+            for slot in slots.iter_mut() {
+                slot.retain(|&(due, id)| keep(due, id));
+            }
+            self.counts[level] = slots.iter().map(Vec::len).sum();
+        }
+        self.overflow.retain(|&(due, id)| keep(due, id));
+        self.ready.retain(|Reverse((due, id))| keep(*due, *id));
+    }
+
+    /// Processes every tick up to `tick`, moving expired items to `ready`.
+    fn advance(&mut self, tick: u64) {
+        while self.current < tick {
+            // Skip over spans of ticks that can't hold any item.
+            let empty_levels = self.counts.iter().take_while(|count| **count == 0).count();
+            if empty_levels == LEVELS && self.overflow.is_empty() {
+                self.current = tick;
+                break;
+            }
+            if empty_levels > 0 {
+                let skip_to = self.current | (span(empty_levels) - 1);
+                if skip_to >= tick {
+                    self.current = tick;
+                    break;
+                }
+                self.current = skip_to;
+            }
+
+            let next = self.current + 1;
+            // Cascaded items are placed relative to the tick being processed, so that
+            // none of them lands back in the slot it was taken from.
+            self.current = next;
+            if next % span(LEVELS) == 0 {
+                let overflow = std::mem::take(&mut self.overflow);
+                for (due, id) in overflow {
+                    self.push(due, id);
+                }
+            }
+            for level in (1..LEVELS).rev() {
+                if next % span(level) == 0 {
+                    let slot = (next >> (BITS as usize * level)) & MASK;
+                    let items = std::mem::take(&mut self.slots[level][slot as usize]);
+                    self.counts[level] -= items.len();
+                    for (due, id) in items {
+                        self.push(due, id);
+                    }
+                }
+            }
+
+            let items = std::mem::take(&mut self.slots[0][(next & MASK) as usize]);
+            self.counts[0] -= items.len();
+            for item in items {
+                self.ready.push(Reverse(item));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn matches_sorted_order_for_random_items() {
+        let mut rng = SplitMix64::new(3);
+        let mut wheel = TimerWheel::new(1_000);
+        let mut dues = vec![];
+        let mut now = 0;
+        let mut expired = vec![];
+        for id in 0..5_000 {
+            let due = now + rng.next_u64() % 50_000_000;
+            wheel.push(due, id);
+            dues.push(due);
+            now += rng.next_u64() % 20_000;
+            while let Some((due, _)) = wheel.pop_due(now) {
+                expired.push(due);
+            }
+        }
+        while let Some((due, _)) = wheel.pop_due(u64::MAX / 2) {
+            expired.push(due);
+        }
+        dues.sort();
+        assert_eq!(expired, dues);
+    }
+
+    #[test]
+    fn peek_finds_the_soonest_live_item() {
+        let mut rng = SplitMix64::new(5);
+        let mut wheel = TimerWheel::new(100);
+        let mut items = vec![];
+        let mut now = 0;
+        for id in 0..3_000 {
+            let due = now + rng.next_u64() % 5_000_000_000;
+            wheel.push(due, id);
+            items.push((due, id));
+            now += rng.next_u64() % 200_000;
+            while let Some(item) = wheel.pop_due(now) {
+                items.retain(|other| *other != item);
+            }
+
+            // Items with an even id are stale.
+            let live = |_: u64, id: u64| id % 2 == 1;
+            let expected = items.iter().filter(|item| live(item.0, item.1)).min();
+            let mut removed = 0;
+            assert_eq!(
+                wheel.peek(live, &mut removed),
+                expected.map(|&(due, _)| due)
+            );
+        }
+    }
+
+    #[test]
+    fn retain_drops_items_from_every_level() {
+        let mut wheel = TimerWheel::new(1);
+        for due in &[2, 100, 10_000, 1_000_000, 100_000_000] {
+            wheel.push(*due, *due);
+            wheel.push(*due, 0);
+        }
+        wheel.retain(|_, id| id != 0);
+        assert_eq!(wheel.counts.iter().sum::<usize>() + wheel.overflow.len(), 5);
+
+        let mut expired = vec![];
+        while let Some((due, _)) = wheel.pop_due(u64::MAX / 2) {
+            expired.push(due);
+        }
+        assert_eq!(expired, vec![2, 100, 10_000, 1_000_000, 100_000_000]);
+    }
+
+    #[test]
+    fn expires_in_due_order_across_levels() {
+        let mut wheel = TimerWheel::new(1);
+        let dues = [5, 70, 64, 127, 4_100, 8_191, 300_000, 20_000_000, 3, 64];
+        for (id, due) in dues.iter().enumerate() {
+            wheel.push(*due, id as u64);
+        }
+
+        let mut expired = vec![];
+        for now in &[10, 100, 5_000, 1_000_000, 30_000_000] {
+            while let Some((due, _)) = wheel.pop_due(*now) {
+                assert!(due <= *now);
+                expired.push(due);
+            }
+        }
+        let mut sorted = dues.to_vec();
+        sorted.sort();
+        assert_eq!(expired, sorted);
+    }
+}